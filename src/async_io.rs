@@ -0,0 +1,276 @@
+//! Async encode/decode over tokio's `AsyncRead`/`AsyncWrite`, enabled by the `tokio` feature.
+//!
+//! Values are encoded with `XDRSerializer` and decoded with `XDRDeserializer`,
+//! so the bytes on the wire are exactly those of `to_bytes`.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::de::from_bytes_with_limits;
+use crate::error::{Error, LimitKind, Result};
+use crate::limits::check_limit;
+use crate::read::io_error;
+use crate::{DecodeLimits, U32_SIZE, to_writer};
+
+/// largest input `from_async_reader` reads and largest frame
+/// `from_async_reader_framed` accepts; the `_with_limits` readers take
+/// `usize::MAX` for no limit
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 << 20;
+
+/// encode `value`, write it to `writer` and flush it
+pub async fn to_async_writer<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, value)?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// read `reader` until EOF and decode a single value from everything read,
+/// failing with `Error::LimitExceeded` past `DEFAULT_MAX_FRAME_LEN` bytes
+pub async fn from_async_reader<R, T>(reader: R) -> Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    from_async_reader_with_limits(reader, DEFAULT_MAX_FRAME_LEN, DecodeLimits::default()).await
+}
+
+/// like `from_async_reader`, failing with `Error::LimitExceeded` once more
+/// than `max_len` bytes are read, and decoding within `limits`
+pub async fn from_async_reader_with_limits<R, T>(
+    reader: R,
+    max_len: usize,
+    limits: DecodeLimits,
+) -> Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    // one byte more than allowed tells inputs over the limit from those right at it
+    let mut bytes = Vec::new();
+    reader
+        .take((max_len as u64).saturating_add(1))
+        .read_to_end(&mut bytes)
+        .await?;
+    check_limit(LimitKind::FrameLen, max_len, bytes.len())?;
+    from_bytes_with_limits(&bytes, limits)
+}
+
+/// encode `value` and write it prefixed with its length as a u32,
+/// which is the same as writing it as an XDR `opaque<>`, then flush it
+pub async fn to_async_writer_framed<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let mut bytes = vec![0u8; U32_SIZE];
+    to_writer(&mut bytes, value)?;
+    let len = u32::try_from(bytes.len() - U32_SIZE).map_err(|_| Error::LengthOverflow)?;
+    bytes[..U32_SIZE].copy_from_slice(&len.to_be_bytes());
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// read one frame written by `to_async_writer_framed` and decode it,
+/// leaving anything after the frame unread; frames announcing more than
+/// `DEFAULT_MAX_FRAME_LEN` bytes fail with `Error::LimitExceeded`
+pub async fn from_async_reader_framed<R, T>(reader: R) -> Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    from_async_reader_framed_with_limits(reader, DEFAULT_MAX_FRAME_LEN, DecodeLimits::default())
+        .await
+}
+
+/// like `from_async_reader_framed`, failing with `Error::LimitExceeded` on
+/// frames announcing more than `max_frame_len` bytes before reading them,
+/// and decoding within `limits`
+pub async fn from_async_reader_framed_with_limits<R, T>(
+    mut reader: R,
+    max_frame_len: usize,
+    limits: DecodeLimits,
+) -> Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut len = [0u8; U32_SIZE];
+    reader.read_exact(&mut len).await.map_err(io_error)?;
+    let len = u32::from_be_bytes(len) as usize;
+    check_limit(LimitKind::FrameLen, max_frame_len, len)?;
+    // read through `take`, so a bogus length cannot force a huge allocation
+    let mut bytes = Vec::new();
+    (&mut reader)
+        .take(len as u64)
+        .read_to_end(&mut bytes)
+        .await?;
+    if bytes.len() < len {
+        return Err(Error::EndOfFile);
+    }
+    from_bytes_with_limits(&bytes, limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct MyStruct {
+        a: u32,
+        b: String,
+    }
+
+    #[tokio::test]
+    async fn test_async_round_trip() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let value = MyStruct {
+            a: 42,
+            b: "hello".to_string(),
+        };
+        to_async_writer(&mut client, &value).await.unwrap();
+        drop(client);
+        let decoded: MyStruct = from_async_reader(server).await.unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[tokio::test]
+    async fn test_async_framed_round_trip() {
+        let (mut client, mut server) = tokio::io::duplex(8);
+        let values = [
+            MyStruct {
+                a: 1,
+                b: "one".to_string(),
+            },
+            MyStruct {
+                a: 2,
+                b: "two".to_string(),
+            },
+        ];
+        let writer = async {
+            for value in &values {
+                to_async_writer_framed(&mut client, value).await.unwrap();
+            }
+        };
+        let reader = async {
+            let mut decoded = Vec::new();
+            for _ in 0..values.len() {
+                let value: MyStruct = from_async_reader_framed(&mut server).await.unwrap();
+                decoded.push(value);
+            }
+            decoded
+        };
+        let ((), decoded) = tokio::join!(writer, reader);
+        assert_eq!(decoded, values);
+    }
+
+    #[tokio::test]
+    async fn test_async_framed_with_truncated_frame() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(&[0, 0, 0, 8, 0, 0, 0, 1]).await.unwrap();
+        drop(client);
+        let result: Result<MyStruct> = from_async_reader_framed(&mut server).await;
+        assert!(matches!(result, Err(Error::EndOfFile)));
+    }
+
+    #[tokio::test]
+    async fn test_async_flush() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut writer = tokio::io::BufWriter::new(client);
+        to_async_writer(&mut writer, &1u32).await.unwrap();
+        to_async_writer_framed(&mut writer, &2u32).await.unwrap();
+        // dropping a tokio BufWriter discards what it still buffers
+        drop(writer);
+        let mut bytes = Vec::new();
+        server.read_to_end(&mut bytes).await.unwrap();
+        assert_eq!(bytes, [0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_async_limits() {
+        let value = MyStruct {
+            a: 42,
+            b: "hello".to_string(),
+        };
+        let len = crate::serialize_len(&value).unwrap();
+
+        // a peer announcing a frame far longer than it sends
+        let wire = [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+        let result: Result<MyStruct> = from_async_reader_framed(&wire[..]).await;
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::FrameLen,
+                limit: DEFAULT_MAX_FRAME_LEN,
+                ..
+            })
+        ));
+        let result: Result<MyStruct> =
+            from_async_reader_framed_with_limits(&wire[..], 1024, DecodeLimits::default()).await;
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::FrameLen,
+                limit: 1024,
+                actual: 0xffff_ffff,
+            })
+        ));
+
+        let mut framed = Vec::new();
+        to_async_writer_framed(&mut framed, &value).await.unwrap();
+        let decoded: MyStruct =
+            from_async_reader_framed_with_limits(&framed[..], len, DecodeLimits::default())
+                .await
+                .unwrap();
+        assert_eq!(decoded, value);
+        let result: Result<MyStruct> =
+            from_async_reader_framed_with_limits(&framed[..], len - 1, DecodeLimits::default())
+                .await;
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::FrameLen,
+                ..
+            })
+        ));
+
+        let bytes = crate::to_bytes(&value).unwrap();
+        let decoded: MyStruct =
+            from_async_reader_with_limits(&bytes[..], len, DecodeLimits::default())
+                .await
+                .unwrap();
+        assert_eq!(decoded, value);
+        let result: Result<MyStruct> =
+            from_async_reader_with_limits(&bytes[..], len - 1, DecodeLimits::default()).await;
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::FrameLen,
+                limit,
+                actual,
+            }) if limit == len - 1 && actual == len
+        ));
+
+        let limits = DecodeLimits {
+            max_bytes_len: 4,
+            ..Default::default()
+        };
+        let result: Result<MyStruct> = from_async_reader_with_limits(&bytes[..], len, limits).await;
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::BytesLen,
+                ..
+            })
+        ));
+    }
+}
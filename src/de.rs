@@ -1,169 +1,375 @@
-use serde::Deserialize;
-use serde::de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use std::io;
+use std::marker::PhantomData;
 
-use crate::error::{Error, Result};
+use serde::Deserialize;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+
+use crate::error::{Error, LimitKind, Result};
+use crate::limits::{DecodeLimits, check_limit};
+use crate::read::{IoRead, Read, Reference, SliceRead};
+use crate::universal_addr::{self, UniversalAddr};
 use crate::{U32_SIZE, U64_SIZE, padding_len};
 
 #[derive(Debug)]
-pub struct XDRDeserializer<'de> {
-    pub(crate) input: &'de [u8],
+pub struct XDRDeserializer<'de, R = SliceRead<'de>> {
+    read: R,
+    scratch: Vec<u8>,
+    limits: DecodeLimits,
+    allocated: usize,
+    depth: usize,
+    path: Vec<Segment>,
+    marker: PhantomData<&'de ()>,
+}
+
+/// one step of the path reported in `Error::Located`
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    /// name of the outermost struct
+    Name(&'static str),
+    Field(&'static str),
+    Index(usize),
+}
+
+impl<'de, R: Read<'de>> XDRDeserializer<'de, R> {
+    pub fn new(read: R) -> Self {
+        XDRDeserializer {
+            read,
+            scratch: Vec::new(),
+            limits: DecodeLimits::default(),
+            allocated: 0,
+            depth: 0,
+            path: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn set_limits(&mut self, limits: DecodeLimits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// attach the current offset and path to `err`, unless an inner value already did
+    fn locate(&self, err: Error) -> Error {
+        if let Error::Located { .. } = err {
+            return err;
+        }
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                Segment::Name(name) => path.push_str(name),
+                Segment::Field(field) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(field);
+                }
+                Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+        Error::Located {
+            offset: self.read.position(),
+            path,
+            error: Box::new(err),
+        }
+    }
+
+    /// run `f` for the value at `segment`, locating any error it returns
+    fn at<T>(&mut self, segment: Segment, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.path.push(segment);
+        let result = f(self).map_err(|err| self.locate(err));
+        self.path.pop();
+        result
+    }
+
+    /// decode a value of type `T` and throw it away, where `IgnoredAny` cannot
+    /// work because XDR does not describe the shape of its values
+    pub fn skip<T: Deserialize<'de>>(&mut self) -> Result<()> {
+        T::deserialize(&mut *self)?;
+        Ok(())
+    }
+
+    /// skip an `opaque<>` or `string` without decoding or keeping its data;
+    /// its length is checked against `max_bytes_len` but, as nothing is
+    /// allocated, not counted against `max_alloc_bytes`
+    pub fn skip_opaque(&mut self) -> Result<()> {
+        let len = self.parse_u32()? as usize;
+        check_limit(LimitKind::BytesLen, self.limits.max_bytes_len, len)?;
+        self.read.skip(len)?;
+        Self::parse_zero_padding(&mut self.read, len)
+    }
+
+    /// number of bytes consumed so far
+    pub fn position(&self) -> usize {
+        self.read.position()
+    }
+
+    /// iterate over back-to-back values of type `T` until the input ends,
+    /// named after serde_json's `Deserializer::into_iter`
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T: Deserialize<'de>>(self) -> StreamDeserializer<'de, R, T> {
+        StreamDeserializer {
+            de: self,
+            offset: 0,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+
+    /// check that all input has been consumed
+    pub fn end(&mut self) -> Result<()> {
+        if self.read.is_empty()? {
+            Ok(())
+        } else {
+            Err(Error::TrailingBytes)
+        }
+    }
 }
 
 impl<'de> XDRDeserializer<'de> {
     pub fn from_bytes(input: &'de [u8]) -> Self {
-        XDRDeserializer { input }
+        XDRDeserializer::new(SliceRead::new(input))
+    }
+
+    /// input following the values decoded so far
+    pub fn remaining(&self) -> &'de [u8] {
+        self.read.remaining()
+    }
+}
+
+impl<'de, R: io::Read> XDRDeserializer<'de, IoRead<R>> {
+    pub fn from_reader(reader: R) -> Self {
+        XDRDeserializer::new(IoRead::new(reader))
     }
 }
 
 pub fn from_bytes<'a, T>(s: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_bytes_with_limits(s, DecodeLimits::default())
+}
+
+/// `from_bytes` within `limits` instead of the default ones
+pub fn from_bytes_with_limits<'a, T>(s: &'a [u8], limits: DecodeLimits) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = XDRDeserializer::from_bytes(s).with_limits(limits);
+    let t = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(err))?;
+    deserializer.end().map_err(|err| deserializer.locate(err))?;
+    Ok(t)
+}
+
+/// decode a value from the start of `s`, returning it with the bytes that follow,
+/// e.g. a header and the payload after it
+pub fn from_bytes_prefix<'a, T>(s: &'a [u8]) -> Result<(T, &'a [u8])>
 where
     T: Deserialize<'a>,
 {
     let mut deserializer = XDRDeserializer::from_bytes(s);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::TrailingBytes)
+    let t = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(err))?;
+    Ok((t, deserializer.remaining()))
+}
+
+/// decode a value from `reader`, pulling only the bytes it needs;
+/// like `from_bytes`, the reader must be exhausted afterwards
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = XDRDeserializer::from_reader(reader);
+    let t = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(err))?;
+    deserializer.end().map_err(|err| deserializer.locate(err))?;
+    Ok(t)
+}
+
+/// Iterator decoding values of type `T` one after another, as found in
+/// log files and record streams, created with `XDRDeserializer::into_iter`.
+///
+/// Iteration stops at the end of the input, or after yielding the first error,
+/// which carries the offset of the failure like `from_bytes` errors do.
+/// A `T` taking no bytes, such as `()`, fails with `Error::TrailingBytes`
+/// unless the input is empty.
+/// `max_alloc_bytes` applies to each value separately.
+pub struct StreamDeserializer<'de, R, T> {
+    de: XDRDeserializer<'de, R>,
+    offset: usize,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<'de, R: Read<'de>, T: Deserialize<'de>> StreamDeserializer<'de, R, T> {
+    pub fn new(read: R) -> Self {
+        XDRDeserializer::new(read).into_iter()
+    }
+
+    /// bytes taken by the values decoded successfully so far,
+    /// which is where the next value starts
+    pub fn byte_offset(&self) -> usize {
+        self.offset
     }
 }
 
-impl<'de> XDRDeserializer<'de> {
-    fn parse_bool(&mut self) -> Result<bool> {
-        let v = u32::from_be_bytes(
-            self.input
-                .get(..U32_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
+impl<'de, R: Read<'de>, T: Deserialize<'de>> Iterator for StreamDeserializer<'de, R, T> {
+    type Item = Result<T>;
 
-        self.input = &self.input[U32_SIZE..];
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        let start = self.de.position();
+        let result = match self.de.read.is_empty() {
+            Ok(true) => return None,
+            Ok(false) => {
+                self.de.allocated = 0;
+                T::deserialize(&mut self.de)
+            }
+            Err(err) => Err(err),
+        };
+        match result {
+            // a value taking no bytes, such as `()`, would repeat forever
+            Ok(_) if self.de.position() == start => {
+                self.failed = true;
+                Some(Err(self.de.locate(Error::TrailingBytes)))
+            }
+            Ok(value) => {
+                self.offset = self.de.position();
+                Some(Ok(value))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(self.de.locate(err)))
+            }
+        }
+    }
+}
+
+impl<'de, R: Read<'de>> XDRDeserializer<'de, R> {
+    fn parse_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.read.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// count `len` bytes against `max_alloc_bytes`
+    fn charge(&mut self, len: usize) -> Result<()> {
+        self.allocated = self.allocated.saturating_add(len);
+        check_limit(
+            LimitKind::AllocBytes,
+            self.limits.max_alloc_bytes,
+            self.allocated,
+        )
+    }
+
+    /// run `f` one nesting level deeper, bounded by `max_depth`
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        check_limit(LimitKind::Depth, self.limits.max_depth, self.depth + 1)?;
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_seq_len(&mut self) -> Result<usize> {
+        let len = self.parse_u32()? as usize;
+        check_limit(LimitKind::SeqLen, self.limits.max_seq_len, len)?;
+        self.charge(len.saturating_mul(U32_SIZE))?;
+        Ok(len)
+    }
+
+    fn parse_bytes_len(&mut self) -> Result<usize> {
+        let len = self.parse_u32()? as usize;
+        check_limit(LimitKind::BytesLen, self.limits.max_bytes_len, len)?;
+        self.charge(len)?;
+        Ok(len)
+    }
+
+    fn parse_bool(&mut self) -> Result<bool> {
+        let v = self.parse_u32()?;
         match v {
             0 => Ok(false),
             1 => Ok(true),
-            _ => Err(Error::Message(format!(
-                "Option type descriminator should be 1 or 0 not {:?}",
-                v
-            ))),
+            _ => Err(Error::InvalidBool(v)),
         }
     }
 
     fn parse_u32(&mut self) -> Result<u32> {
-        let v = u32::from_be_bytes(
-            self.input
-                .get(..U32_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
-
-        self.input = &self.input[U32_SIZE..];
-        Ok(v)
+        Ok(u32::from_be_bytes(self.parse_array::<U32_SIZE>()?))
     }
 
     fn parse_u64(&mut self) -> Result<u64> {
-        let v = u64::from_be_bytes(
-            self.input
-                .get(..U64_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
-
-        self.input = &self.input[U64_SIZE..];
-        Ok(v)
+        Ok(u64::from_be_bytes(self.parse_array::<U64_SIZE>()?))
     }
-    fn parse_i32(&mut self) -> Result<i32> {
-        let v = i32::from_be_bytes(
-            self.input
-                .get(..U32_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
 
-        self.input = &self.input[U32_SIZE..];
-        Ok(v)
+    fn parse_i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.parse_array::<U32_SIZE>()?))
     }
 
     fn parse_i64(&mut self) -> Result<i64> {
-        let v = i64::from_be_bytes(
-            self.input
-                .get(..U64_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
-
-        self.input = &self.input[U64_SIZE..];
-        Ok(v)
+        Ok(i64::from_be_bytes(self.parse_array::<U64_SIZE>()?))
     }
 
     fn parse_f32(&mut self) -> Result<f32> {
-        let v = f32::from_be_bytes(
-            self.input
-                .get(..U32_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
-
-        self.input = &self.input[U32_SIZE..];
-        Ok(v)
+        Ok(f32::from_be_bytes(self.parse_array::<U32_SIZE>()?))
     }
 
     fn parse_f64(&mut self) -> Result<f64> {
-        let v = f64::from_be_bytes(
-            self.input
-                .get(..U64_SIZE)
-                .ok_or(Error::EndOfFile)?
-                .try_into()
-                .map_err(|_| Error::EndOfFile)?,
-        );
-
-        self.input = &self.input[U64_SIZE..];
-        Ok(v)
+        Ok(f64::from_be_bytes(self.parse_array::<U64_SIZE>()?))
     }
 
-    fn parse_bytes(&mut self) -> Result<Vec<u8>> {
-        let len = self.parse_u32()?;
-        let padded_len = len as usize + padding_len(len as usize);
-        if self.input.len() < padded_len {
-            return Err(Error::EndOfFile);
-        }
+    /// consume the padding following `len` bytes of opaque or string data
+    fn parse_padding(read: &mut R, len: usize) -> Result<[u8; 3]> {
+        let mut padding = [0u8; 3];
+        read.read_exact(&mut padding[..padding_len(len)])?;
+        Ok(padding)
+    }
 
-        let v = self.input[..len as usize].to_vec();
-        if self.input[len as usize..padded_len].iter().any(|&b| b != 0) {
+    /// consume the padding following `len` bytes of opaque data, which must be zero
+    fn parse_zero_padding(read: &mut R, len: usize) -> Result<()> {
+        if Self::parse_padding(read, len)?.iter().any(|&b| b != 0) {
             return Err(Error::NonZeroPadding);
         }
-        self.input = &self.input[padded_len..];
+        Ok(())
+    }
+
+    fn parse_bytes(&mut self) -> Result<Reference<'de, '_, [u8]>> {
+        let len = self.parse_bytes_len()?;
+        let v = self.read.read_slice(len, &mut self.scratch)?;
+        Self::parse_zero_padding(&mut self.read, len)?;
         Ok(v)
     }
 
-    fn parse_str(&mut self) -> Result<&'de str> {
-        let len = self.parse_u32()?;
-        let padded_len = len as usize + padding_len(len as usize);
-        if self.input.len() < padded_len {
-            return Err(Error::EndOfFile);
+    fn parse_str(&mut self) -> Result<Reference<'de, '_, str>> {
+        let len = self.parse_bytes_len()?;
+        let v = self.read.read_slice(len, &mut self.scratch)?;
+        Self::parse_padding(&mut self.read, len)?;
+        match v {
+            Reference::Borrowed(b) => Ok(Reference::Borrowed(std::str::from_utf8(b)?)),
+            Reference::Copied(c) => Ok(Reference::Copied(std::str::from_utf8(c)?)),
         }
-        let v = &self.input[..len as usize];
-        self.input = &self.input[padded_len..];
-        let s = std::str::from_utf8(v)?;
-        Ok(s)
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
+impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut XDRDeserializer<'de, R> {
     type Error = Error;
 
     fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::Message(
-            "inner error, deserialize_any is not support".to_owned(),
-        ))
+        Err(Error::NotSelfDescribing)
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -173,18 +379,22 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         visitor.visit_bool(self.parse_bool()?)
     }
 
+    // opaque data is handed out borrowed from the input whenever it is a slice
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_byte_buf(visitor)
+        match self.parse_bytes()? {
+            Reference::Borrowed(b) => visitor.visit_borrowed_bytes(b),
+            Reference::Copied(c) => visitor.visit_bytes(c),
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let bytes = self.parse_bytes()?;
+        let bytes = self.parse_bytes()?.to_vec();
         visitor.visit_byte_buf(bytes)
     }
 
@@ -193,7 +403,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         V: Visitor<'de>,
     {
         let v = self.parse_i32()?;
-        let v = i8::try_from(v).map_err(|e| Self::Error::Message(format!("{:?}", e)))?;
+        let v = i8::try_from(v).map_err(|_| Error::IntegerOutOfRange {
+            expected_type: "i8",
+            value: v as i64,
+        })?;
         visitor.visit_i8(v)
     }
 
@@ -202,7 +415,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         V: Visitor<'de>,
     {
         let v = self.parse_i32()?;
-        let v = i16::try_from(v).map_err(|e| Self::Error::Message(format!("{:?}", e)))?;
+        let v = i16::try_from(v).map_err(|_| Error::IntegerOutOfRange {
+            expected_type: "i16",
+            value: v as i64,
+        })?;
 
         visitor.visit_i16(v)
     }
@@ -226,7 +442,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         V: Visitor<'de>,
     {
         let v = self.parse_u32()?;
-        let v = u8::try_from(v).map_err(|e| Self::Error::Message(format!("{:?}", e)))?;
+        let v = u8::try_from(v).map_err(|_| Error::IntegerOutOfRange {
+            expected_type: "u8",
+            value: v as i64,
+        })?;
 
         visitor.visit_u8(v)
     }
@@ -236,7 +455,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         V: Visitor<'de>,
     {
         let v = self.parse_u32()?;
-        let v = u16::try_from(v).map_err(|e| Self::Error::Message(format!("{:?}", e)))?;
+        let v = u16::try_from(v).map_err(|_| Error::IntegerOutOfRange {
+            expected_type: "u16",
+            value: v as i64,
+        })?;
 
         visitor.visit_u16(v)
     }
@@ -274,7 +496,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         V: Visitor<'de>,
     {
         let v = self.parse_u32()?;
-        let v = char::try_from(v).map_err(|e| Self::Error::Message(format!("{:?}", e)))?;
+        let v = char::try_from(v).map_err(|_| Error::InvalidChar(v))?;
 
         visitor.visit_char(v)
     }
@@ -283,7 +505,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.parse_str()?)
+        match self.parse_str()? {
+            Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Reference::Copied(s) => visitor.visit_str(s),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -299,7 +524,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
     {
         let descriminator = self.parse_bool()?;
         if descriminator {
-            visitor.visit_some(self)
+            self.nested(|de| visitor.visit_some(de))
         } else {
             visitor.visit_none()
         }
@@ -319,10 +544,19 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        // see `universal_addr::NEWTYPE_NAME`
+        if name == universal_addr::NEWTYPE_NAME {
+            let uaddr = self.parse_str()?;
+            uaddr.parse::<UniversalAddr>()?;
+            return match uaddr {
+                Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Reference::Copied(s) => visitor.visit_str(s),
+            };
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -330,15 +564,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let len = self.parse_u32()?;
-        let value = visitor.visit_seq(LengthAccessor::new(self, len as usize))?;
-        Ok(value)
+        let len = self.parse_seq_len()?;
+        self.nested(|de| visitor.visit_seq(LengthAccessor::new(de, len)))
     }
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(LengthAccessor::new(self, len))
+        self.nested(|de| visitor.visit_seq(LengthAccessor::new(de, len)))
     }
 
     fn deserialize_tuple_struct<V>(
@@ -350,28 +583,36 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(LengthAccessor::new(self, len))
+        self.nested(|de| visitor.visit_seq(LengthAccessor::new(de, len)))
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let len = self.parse_u32()?;
-        let map_accessor = LengthAccessor::new(self, len as usize);
-        visitor.visit_map(map_accessor)
+        let len = self.parse_seq_len()?;
+        self.nested(|de| visitor.visit_map(LengthAccessor::new(de, len)))
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(LengthAccessor::new(self, fields.len()))
+        // paths start at the outermost struct, e.g. `READDIR3resok.reply.entries[3].name`
+        let root = self.path.is_empty();
+        if root {
+            self.path.push(Segment::Name(name));
+        }
+        let result = self.nested(|de| visitor.visit_seq(LengthAccessor::with_fields(de, fields)));
+        if root {
+            self.path.pop();
+        }
+        result
     }
 
     fn deserialize_enum<V>(
@@ -383,7 +624,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(MyEnumAccess::new(self))
+        self.nested(|de| visitor.visit_enum(MyEnumAccess::new(de)))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
@@ -393,29 +634,50 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut XDRDeserializer<'de> {
         self.deserialize_str(visitor)
     }
 
+    // nothing on the wire tells how long an unknown value is, see
+    // `XDRDeserializer::skip` for skipping values of a known type
     fn deserialize_ignored_any<V>(self, _visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(Error::NotSelfDescribing)
     }
 }
 
-struct LengthAccessor<'a, 'de: 'a> {
-    de: &'a mut XDRDeserializer<'de>,
+struct LengthAccessor<'a, 'de: 'a, R> {
+    de: &'a mut XDRDeserializer<'de, R>,
     remain_items: usize,
+    index: usize,
+    fields: &'static [&'static str],
 }
 
-impl<'a, 'de> LengthAccessor<'a, 'de> {
-    fn new(de: &'a mut XDRDeserializer<'de>, count: usize) -> Self {
+impl<'a, 'de, R> LengthAccessor<'a, 'de, R> {
+    fn new(de: &'a mut XDRDeserializer<'de, R>, count: usize) -> Self {
         Self {
             de,
             remain_items: count,
+            index: 0,
+            fields: &[],
+        }
+    }
+
+    fn with_fields(de: &'a mut XDRDeserializer<'de, R>, fields: &'static [&'static str]) -> Self {
+        Self {
+            fields,
+            ..Self::new(de, fields.len())
+        }
+    }
+
+    /// path segment of the item at `index`
+    fn segment(&self, index: usize) -> Segment {
+        match self.fields.get(index) {
+            Some(field) => Segment::Field(field),
+            None => Segment::Index(index),
         }
     }
 }
 
-impl<'de, 'a> SeqAccess<'de> for LengthAccessor<'a, 'de> {
+impl<'de, 'a, R: Read<'de>> SeqAccess<'de> for LengthAccessor<'a, 'de, R> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
@@ -426,7 +688,11 @@ impl<'de, 'a> SeqAccess<'de> for LengthAccessor<'a, 'de> {
             return Ok(None);
         }
         self.remain_items -= 1;
-        seed.deserialize(&mut *self.de).map(Some)
+        self.index += 1;
+        let segment = self.segment(self.index - 1);
+        self.de
+            .at(segment, |de| seed.deserialize(&mut *de))
+            .map(Some)
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -434,7 +700,7 @@ impl<'de, 'a> SeqAccess<'de> for LengthAccessor<'a, 'de> {
     }
 }
 
-impl<'a, 'de> MapAccess<'de> for LengthAccessor<'a, 'de> {
+impl<'a, 'de, R: Read<'de>> MapAccess<'de> for LengthAccessor<'a, 'de, R> {
     type Error = Error;
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
@@ -444,28 +710,31 @@ impl<'a, 'de> MapAccess<'de> for LengthAccessor<'a, 'de> {
             return Ok(None);
         }
         self.remain_items -= 1;
-        Ok(Some(seed.deserialize(&mut *self.de)?))
+        self.index += 1;
+        let segment = Segment::Index(self.index - 1);
+        Ok(Some(self.de.at(segment, |de| seed.deserialize(&mut *de))?))
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        let segment = Segment::Index(self.index.saturating_sub(1));
+        self.de.at(segment, |de| seed.deserialize(&mut *de))
     }
 }
 
-struct MyEnumAccess<'a, 'de: 'a> {
-    de: &'a mut XDRDeserializer<'de>,
+struct MyEnumAccess<'a, 'de: 'a, R> {
+    de: &'a mut XDRDeserializer<'de, R>,
 }
 
-impl<'a, 'de> MyEnumAccess<'a, 'de> {
-    fn new(de: &'a mut XDRDeserializer<'de>) -> Self {
+impl<'a, 'de, R> MyEnumAccess<'a, 'de, R> {
+    fn new(de: &'a mut XDRDeserializer<'de, R>) -> Self {
         Self { de }
     }
 }
 
-impl<'a, 'de> EnumAccess<'de> for MyEnumAccess<'a, 'de> {
+impl<'a, 'de, R: Read<'de>> EnumAccess<'de> for MyEnumAccess<'a, 'de, R> {
     type Error = Error;
     type Variant = Self;
 
@@ -478,7 +747,7 @@ impl<'a, 'de> EnumAccess<'de> for MyEnumAccess<'a, 'de> {
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &mut MyEnumAccess<'a, 'de> {
+impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &mut MyEnumAccess<'a, 'de, R> {
     type Error = Error;
     fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
@@ -588,7 +857,10 @@ impl<'de, 'a> de::Deserializer<'de> for &mut MyEnumAccess<'a, 'de> {
             }
         }
         let v = self.deserialize_u32(VarintVisitor {})?;
-        visitor.visit_u32(v)
+        // the only way a variant index is rejected is by not naming any variant
+        visitor
+            .visit_u32::<Error>(v)
+            .map_err(|_| Error::UnknownDiscriminant(v))
     }
     fn deserialize_ignored_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
@@ -728,7 +1000,7 @@ impl<'de, 'a> de::Deserializer<'de> for &mut MyEnumAccess<'a, 'de> {
     }
 }
 
-impl<'de, 'a> VariantAccess<'de> for MyEnumAccess<'a, 'de> {
+impl<'de, 'a, R: Read<'de>> VariantAccess<'de> for MyEnumAccess<'a, 'de, R> {
     type Error = Error;
     fn unit_variant(self) -> std::result::Result<(), Self::Error> {
         Ok(())
@@ -749,7 +1021,7 @@ impl<'de, 'a> VariantAccess<'de> for MyEnumAccess<'a, 'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(LengthAccessor::new(self.de, fields.len()))
+        visitor.visit_seq(LengthAccessor::with_fields(self.de, fields))
     }
 
     fn tuple_variant<V>(self, len: usize, visitor: V) -> std::result::Result<V::Value, Self::Error>
@@ -760,12 +1032,11 @@ impl<'de, 'a> VariantAccess<'de> for MyEnumAccess<'a, 'de> {
     }
 }
 
+/// number of bytes the `T` at the start of `data` is encoded in
 pub fn deserialize_len<'a, T: Deserialize<'a>>(data: &'a [u8]) -> Result<usize> {
-    let total_len = data.len();
     let mut deserializer = XDRDeserializer::from_bytes(data);
-    let _ = T::deserialize(&mut deserializer);
-    let remaining_len = deserializer.input.len();
-    Ok(total_len - remaining_len)
+    T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(err))?;
+    Ok(deserializer.position())
 }
 
 #[cfg(test)]
@@ -947,5 +1218,510 @@ mod tests {
         let len = deserialize_len::<MyStruct>(data).unwrap();
 
         assert_eq!(len, 16);
+
+        let result = deserialize_len::<MyStruct>(&data[..10]);
+        assert!(matches!(
+            result.as_ref().map_err(crate::Error::inner),
+            Err(crate::Error::EndOfFile)
+        ));
+    }
+
+    #[test]
+    fn test_deserialize_stream() {
+        use crate::{Error, StreamDeserializer, XDRDeserializer, read::IoRead};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Record {
+            id: u32,
+            name: String,
+        }
+
+        let data: &[u8] = &[
+            0, 0, 0, 1, 0, 0, 0, 1, b'a', 0, 0, 0, // first record
+            0, 0, 0, 2, 0, 0, 0, 2, b'b', b'c', 0, 0, // second record
+        ];
+        let mut stream = XDRDeserializer::from_bytes(data).into_iter::<Record>();
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(first.id, 1);
+        assert_eq!(stream.byte_offset(), 12);
+        let second = stream.next().unwrap().unwrap();
+        assert_eq!(second.name, "bc");
+        assert_eq!(stream.byte_offset(), 24);
+        assert!(stream.next().is_none());
+
+        let records = XDRDeserializer::from_reader(data)
+            .into_iter::<Record>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, [first, second]);
+
+        let mut stream = StreamDeserializer::<_, Record>::new(IoRead::new(&data[..20]));
+        assert!(stream.next().unwrap().is_ok());
+        let err = stream.next().unwrap().unwrap_err();
+        assert!(matches!(err.inner(), Error::EndOfFile));
+        assert_eq!(err.offset(), Some(20));
+        assert_eq!(stream.byte_offset(), 12);
+        assert!(stream.next().is_none());
+
+        let mut stream = XDRDeserializer::from_bytes(data).into_iter::<()>();
+        let err = stream.next().unwrap().unwrap_err();
+        assert!(matches!(err.inner(), Error::TrailingBytes));
+        assert_eq!(err.offset(), Some(0));
+        assert!(stream.next().is_none());
+        assert!(
+            XDRDeserializer::from_bytes(&[])
+                .into_iter::<()>()
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_deserialize_prefix() {
+        use crate::{XDRDeserializer, from_bytes_prefix};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Header {
+            kind: u32,
+            len: u32,
+        }
+
+        let data: &[u8] = &[
+            0, 0, 0, 1, // kind
+            0, 0, 0, 3, // len
+            7, 8, 9, // payload
+        ];
+        let (header, payload) = from_bytes_prefix::<Header>(data).unwrap();
+        assert_eq!(header, Header { kind: 1, len: 3 });
+        assert_eq!(payload, [7, 8, 9]);
+
+        let mut deserializer = XDRDeserializer::from_bytes(data);
+        deserializer.skip::<u32>().unwrap();
+        assert_eq!(deserializer.position(), 4);
+        assert_eq!(deserializer.remaining(), &data[4..]);
+
+        assert!(from_bytes_prefix::<Header>(&data[..6]).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_from_reader() {
+        use crate::from_reader;
+
+        // hands out one byte per read call, like a slow pipe
+        struct OneByteReader<'a>(&'a [u8]);
+        impl std::io::Read for OneByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match (self.0.split_first(), buf.first_mut()) {
+                    (Some((byte, rest)), Some(first)) => {
+                        *first = *byte;
+                        self.0 = rest;
+                        Ok(1)
+                    }
+                    _ => Ok(0),
+                }
+            }
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct S {
+            a: u16,
+            b: String,
+            c: Option<bool>,
+            d: serde_bytes::ByteBuf,
+        }
+
+        let data: &[u8] = &[
+            0, 0, 0, 42, // u16
+            0, 0, 0, 5, // string_len
+            b'h', b'e', b'l', b'l', b'o', 0, 0, 0, // string with padding
+            0, 0, 0, 1, 0, 0, 0, 1, // Some(true)
+            0, 0, 0, 3, 1, 2, 3, 0, // opaque with padding
+        ];
+        let expected_s = S {
+            a: 42,
+            b: "hello".to_owned(),
+            c: Some(true),
+            d: serde_bytes::ByteBuf::from(vec![1, 2, 3]),
+        };
+        let s: S = from_reader(std::io::Cursor::new(data)).unwrap();
+        assert_eq!(s, expected_s);
+        let s: S = from_reader(OneByteReader(data)).unwrap();
+        assert_eq!(s, expected_s);
+    }
+
+    #[test]
+    fn test_deserialize_from_reader_with_error() {
+        use crate::{Error, from_reader};
+
+        let truncated: &[u8] = &[0, 0, 0, 5, b'h', b'e'];
+        let result: Result<String, Error> = from_reader(truncated);
+        assert!(matches!(result.unwrap_err().inner(), Error::EndOfFile));
+
+        let trailing: &[u8] = &[0, 0, 0, 1, 0];
+        let result: Result<u32, Error> = from_reader(trailing);
+        assert!(matches!(result.unwrap_err().inner(), Error::TrailingBytes));
+
+        let bad_padding: &[u8] = &[0, 0, 0, 1, 1, 0, 0, 1];
+        let result: Result<serde_bytes::ByteBuf, Error> = from_reader(bad_padding);
+        assert!(matches!(result.unwrap_err().inner(), Error::NonZeroPadding));
+
+        let bad_bool: &[u8] = &[0, 0, 0, 2];
+        let result: Result<bool, Error> = from_reader(bad_bool);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_deserialize_with_limits() {
+        use crate::{DecodeLimits, Error, LimitKind, XDRDeserializer};
+
+        fn decode<'a, T: Deserialize<'a>>(
+            data: &'a [u8],
+            limits: DecodeLimits,
+        ) -> Result<T, Error> {
+            let mut deserializer = XDRDeserializer::from_bytes(data).with_limits(limits);
+            T::deserialize(&mut deserializer)
+        }
+
+        // a length prefix of u32::MAX with no data behind it
+        let hostile: &[u8] = &[255, 255, 255, 255];
+        let limits = DecodeLimits {
+            max_seq_len: 16,
+            max_bytes_len: 16,
+            ..Default::default()
+        };
+        let result = decode::<Vec<u32>>(hostile, limits);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::SeqLen,
+                limit: 16,
+                actual: 0xffff_ffff,
+            })
+        ));
+        let result = decode::<std::collections::HashMap<u32, u32>>(hostile, limits);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::SeqLen,
+                ..
+            })
+        ));
+        let result = decode::<String>(hostile, limits);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::BytesLen,
+                ..
+            })
+        ));
+        let result = decode::<serde_bytes::ByteBuf>(hostile, limits);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::BytesLen,
+                ..
+            })
+        ));
+
+        // 4 bytes for each of the two elements plus one byte for each string
+        let data: &[u8] = &[
+            0, 0, 0, 2, // len (u32)
+            0, 0, 0, 1, b'a', 0, 0, 0, // "a"
+            0, 0, 0, 1, b'b', 0, 0, 0, // "b"
+        ];
+        let limits = DecodeLimits {
+            max_alloc_bytes: 10,
+            ..Default::default()
+        };
+        assert_eq!(decode::<Vec<String>>(data, limits).unwrap(), vec!["a", "b"]);
+        let limits = DecodeLimits {
+            max_alloc_bytes: 9,
+            ..Default::default()
+        };
+        let result = decode::<Vec<String>>(data, limits);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::AllocBytes,
+                limit: 9,
+                actual: 10,
+            })
+        ));
+
+        let data: &[u8] = &[
+            0, 0, 0, 1, // len (u32)
+            0, 0, 0, 1, // Some
+            0, 0, 0, 7, // u32
+        ];
+        let limits = DecodeLimits {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            decode::<Vec<Option<u32>>>(data, limits).unwrap(),
+            vec![Some(7)]
+        );
+        let limits = DecodeLimits {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let result = decode::<Vec<Option<u32>>>(data, limits);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::Depth,
+                limit: 1,
+                actual: 2,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_deserialize_recursive_type_with_depth_limit() {
+        use crate::{DecodeLimits, Error, LimitKind, RECOMMENDED_MAX_DEPTH, XDRDeserializer};
+
+        #[derive(Debug, Deserialize)]
+        struct Entry {
+            #[allow(unused)]
+            fileid: u64,
+            next: Option<Box<Entry>>,
+        }
+
+        fn linked_list(len: usize) -> Vec<u8> {
+            let mut data = Vec::new();
+            for i in 0..len as u64 {
+                data.extend([0, 0, 0, 1]); // Some
+                data.extend(i.to_be_bytes()); // fileid
+            }
+            data.extend([0, 0, 0, 0]); // None
+            data
+        }
+
+        // far deeper than the stack could take without the limit
+        let data = linked_list(100_000);
+        let result: Result<Option<Box<Entry>>, Error> = from_bytes(&data);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::Depth,
+                limit: RECOMMENDED_MAX_DEPTH,
+                ..
+            })
+        ));
+
+        // every entry takes two levels, the Option and the struct
+        let data = linked_list(RECOMMENDED_MAX_DEPTH / 2);
+        let list: Option<Box<Entry>> = from_bytes(&data).unwrap();
+        let mut len = 0;
+        let mut entry = list.as_deref();
+        while let Some(e) = entry {
+            len += 1;
+            entry = e.next.as_deref();
+        }
+        assert_eq!(len, RECOMMENDED_MAX_DEPTH / 2);
+
+        let data = linked_list(RECOMMENDED_MAX_DEPTH / 2 + 1);
+        assert!(from_bytes::<Option<Box<Entry>>>(&data).is_err());
+        let limits = DecodeLimits {
+            max_depth: RECOMMENDED_MAX_DEPTH + 2,
+            ..Default::default()
+        };
+        let mut deserializer = XDRDeserializer::from_bytes(&data).with_limits(limits);
+        assert!(Option::<Box<Entry>>::deserialize(&mut deserializer).is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_error_with_location() {
+        use crate::Error;
+
+        #[allow(unused)]
+        #[derive(Debug, Deserialize)]
+        struct Entry {
+            fileid: u64,
+            name: String,
+        }
+
+        #[allow(unused)]
+        #[derive(Debug, Deserialize)]
+        struct DirList {
+            entries: Vec<Entry>,
+            eof: bool,
+        }
+
+        #[allow(unused)]
+        #[derive(Debug, Deserialize)]
+        struct ReaddirResOk {
+            cookieverf: u64,
+            reply: DirList,
+        }
+
+        let mut data = vec![0, 0, 0, 0, 0, 0, 0, 9]; // cookieverf
+        data.extend([0, 0, 0, 4]); // 4 entries
+        for fileid in 0..3u64 {
+            data.extend(fileid.to_be_bytes());
+            data.extend([0, 0, 0, 1, b'a', 0, 0, 0]);
+        }
+        data.extend(3u64.to_be_bytes());
+        data.extend([0, 0, 0, 2, 0xff, 0xfe, 0, 0]); // invalid utf-8
+
+        let err = from_bytes::<ReaddirResOk>(&data).unwrap_err();
+        assert!(matches!(err.inner(), Error::Utf8Error(_)));
+        assert_eq!(err.offset(), Some(data.len()));
+        assert_eq!(err.path(), Some("ReaddirResOk.reply.entries[3].name"));
+        assert!(err.to_string().ends_with(&format!(
+            "at byte {} in ReaddirResOk.reply.entries[3].name",
+            data.len()
+        )));
+
+        let truncated = &data[..16];
+        let err = from_bytes::<ReaddirResOk>(truncated).unwrap_err();
+        assert!(matches!(err.inner(), Error::EndOfFile));
+        assert_eq!(err.offset(), Some(12));
+        assert_eq!(err.path(), Some("ReaddirResOk.reply.entries[0].fileid"));
+
+        let err = from_bytes::<u32>(&[0, 0]).unwrap_err();
+        assert!(matches!(err.inner(), Error::EndOfFile));
+        assert_eq!(err.offset(), Some(0));
+        assert_eq!(err.path(), None);
+    }
+
+    #[test]
+    fn test_deserialize_typed_errors() {
+        use crate::Error;
+
+        fn decode_err<'a, T: Deserialize<'a> + std::fmt::Debug>(data: &'a [u8]) -> Error {
+            from_bytes::<T>(data).unwrap_err()
+        }
+
+        let err = decode_err::<u8>(&[0, 0, 1, 0]);
+        assert!(matches!(
+            err.inner(),
+            Error::IntegerOutOfRange {
+                expected_type: "u8",
+                value: 256
+            }
+        ));
+        let err = decode_err::<u16>(&[0, 1, 0, 0]);
+        assert!(matches!(
+            err.inner(),
+            Error::IntegerOutOfRange {
+                expected_type: "u16",
+                value: 65536
+            }
+        ));
+        let err = decode_err::<i8>(&[255, 255, 255, 0]);
+        assert!(matches!(
+            err.inner(),
+            Error::IntegerOutOfRange {
+                expected_type: "i8",
+                value: -256
+            }
+        ));
+        let err = decode_err::<i16>(&[0, 1, 0, 0]);
+        assert!(matches!(
+            err.inner(),
+            Error::IntegerOutOfRange {
+                expected_type: "i16",
+                value: 65536
+            }
+        ));
+
+        let err = decode_err::<bool>(&[0, 0, 0, 2]);
+        assert!(matches!(err.inner(), Error::InvalidBool(2)));
+        let err = decode_err::<Option<u32>>(&[0, 0, 0, 3, 0, 0, 0, 1]);
+        assert!(matches!(err.inner(), Error::InvalidBool(3)));
+
+        let err = decode_err::<char>(&[0, 0, 0xd8, 0]);
+        assert!(matches!(err.inner(), Error::InvalidChar(0xd800)));
+
+        #[allow(unused)]
+        #[derive(Debug, Deserialize)]
+        enum E {
+            Zero,
+            One(u32),
+        }
+        let err = decode_err::<E>(&[0, 0, 0, 2]);
+        assert!(matches!(err.inner(), Error::UnknownDiscriminant(2)));
+    }
+
+    #[test]
+    fn test_deserialize_borrowed_bytes() {
+        use serde_bytes::Bytes;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Write<'a> {
+            offset: u64,
+            #[serde(borrow)]
+            data: &'a Bytes,
+            raw: &'a [u8],
+        }
+
+        let data: &[u8] = &[
+            0, 0, 0, 0, 0, 0, 0, 8, // offset (u64)
+            0, 0, 0, 5, 1, 2, 3, 4, 5, 0, 0, 0, // opaque with padding
+            0, 0, 0, 2, 6, 7, 0, 0, // opaque with padding
+        ];
+        let write: Write = from_bytes(data).unwrap();
+        assert_eq!(write.offset, 8);
+        assert_eq!(write.data.as_ref(), &[1, 2, 3, 4, 5]);
+        assert_eq!(write.raw, &[6, 7]);
+        // both point into the input instead of a copy
+        assert!(std::ptr::eq(write.data.as_ptr(), data[12..].as_ptr()));
+        assert!(std::ptr::eq(write.raw.as_ptr(), data[24..].as_ptr()));
+
+        let bad_padding: &[u8] = &[0, 0, 0, 1, 1, 0, 1, 0];
+        let err = from_bytes::<&[u8]>(bad_padding).unwrap_err();
+        assert!(matches!(err.inner(), crate::Error::NonZeroPadding));
+    }
+
+    #[test]
+    fn test_deserialize_ignored_any() {
+        use serde::de::IgnoredAny;
+
+        use crate::{Error, XDRDeserializer};
+
+        let err = from_bytes::<IgnoredAny>(&[0, 0, 0, 1]).unwrap_err();
+        assert!(matches!(err.inner(), Error::NotSelfDescribing));
+
+        #[allow(unused)]
+        #[derive(Debug, Deserialize)]
+        struct Header {
+            id: u32,
+            tag: String,
+        }
+
+        let data: &[u8] = &[
+            0, 0, 0, 7, 0, 0, 0, 1, b'a', 0, 0, 0, // Header
+            0, 0, 0, 3, 1, 2, 3, 0, // opaque with padding
+            0, 0, 0, 42, // u32
+        ];
+        let mut deserializer = XDRDeserializer::from_bytes(data);
+        deserializer.skip::<Header>().unwrap();
+        deserializer.skip_opaque().unwrap();
+        assert_eq!(u32::deserialize(&mut deserializer).unwrap(), 42);
+        deserializer.end().unwrap();
+
+        let mut deserializer = XDRDeserializer::from_reader(&data[12..15]);
+        assert!(matches!(deserializer.skip_opaque(), Err(Error::EndOfFile)));
+
+        // skipped data is not allocated, so it does not count as such
+        let limits = crate::DecodeLimits {
+            max_alloc_bytes: 0,
+            ..Default::default()
+        };
+        let mut deserializer = XDRDeserializer::from_bytes(&data[12..]).with_limits(limits);
+        deserializer.skip_opaque().unwrap();
+        assert_eq!(deserializer.position(), 8);
+        let mut deserializer = XDRDeserializer::from_reader(&data[12..]).with_limits(limits);
+        assert!(!deserializer.read.is_empty().unwrap());
+        deserializer.skip_opaque().unwrap();
+        assert_eq!(u32::deserialize(&mut deserializer).unwrap(), 42);
+        deserializer.end().unwrap();
+        let bad_padding: &[u8] = &[0, 0, 0, 3, 1, 2, 3, 1];
+        let mut deserializer = XDRDeserializer::from_bytes(bad_padding);
+        assert!(matches!(
+            deserializer.skip_opaque(),
+            Err(Error::NonZeroPadding)
+        ));
     }
 }
@@ -6,14 +6,111 @@ use serde::{de, ser};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    SeqLen,
+    BytesLen,
+    AllocBytes,
+    Depth,
+    /// length of a record reassembled from record marking fragments
+    RecordLen,
+    /// length of the input read by the async readers, framed or not
+    FrameLen,
+}
+
+impl Display for LimitKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            LimitKind::SeqLen => "sequence length",
+            LimitKind::BytesLen => "opaque/string length",
+            LimitKind::AllocBytes => "total allocated bytes",
+            LimitKind::Depth => "nesting depth",
+            LimitKind::RecordLen => "record length",
+            LimitKind::FrameLen => "frame length",
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
+    /// custom error raised by a `Serialize` or `Deserialize` implementation
     Message(String),
     EndOfFile,
     SequenceWithoutLength,
     TrailingBytes,
     Utf8Error(String),
     NonZeroPadding,
+    /// decoded integer does not fit the requested type
+    IntegerOutOfRange {
+        expected_type: &'static str,
+        value: i64,
+    },
+    /// bool or optional discriminator other than 0 or 1
+    InvalidBool(u32),
+    /// decoded value is not a unicode scalar value
+    InvalidChar(u32),
+    /// enum discriminant matching no variant
+    UnknownDiscriminant(u32),
+    /// length does not fit the u32 length prefix
+    LengthOverflow,
+    /// map key serialized without a value following it
+    MapKeyWithoutValue,
+    /// map value serialized without a key before it
+    MapValueWithoutKey,
+    /// XDR carries no type information, so the type to decode must be known
+    NotSelfDescribing,
+    /// RPC credential or verifier of another flavor than the one being decoded
+    UnexpectedAuthFlavor(u32),
+    /// output slice is shorter than the `needed` bytes of the encoding
+    BufferTooSmall {
+        needed: usize,
+    },
+    /// string that is not a universal address such as `192.0.2.1.8.1`
+    InvalidUniversalAddr(String),
+    /// NFSv4 attribute number without a known `fattr4` encoding
+    UnknownAttr(u32),
+    /// record marking fragment length outside `1..=MAX_FRAGMENT_LEN`
+    InvalidFragmentLen(usize),
+    Io(std::io::Error),
+    LimitExceeded {
+        kind: LimitKind,
+        limit: usize,
+        actual: usize,
+    },
+    /// `error` raised after consuming `offset` bytes, while decoding the value at `path`;
+    /// since 0.2 every decoding error comes wrapped in it, so match on `inner()`
+    /// rather than on the error itself
+    Located {
+        offset: usize,
+        path: String,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// byte offset at which decoding failed, if known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::Located { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// path of the value that failed to decode, such as `READDIR3resok.reply.entries[3].name`
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::Located { path, .. } if !path.is_empty() => Some(path),
+            _ => None,
+        }
+    }
+
+    /// the error itself, without location
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Located { error, .. } => error,
+            _ => self,
+        }
+    }
 }
 
 impl ser::Error for Error {
@@ -34,6 +131,12 @@ impl From<Utf8Error> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -45,8 +148,72 @@ impl Display for Error {
             Error::TrailingBytes => formatter.write_str("not all input bytes are comsumed"),
             Error::Utf8Error(msg) => formatter.write_str(msg),
             Error::NonZeroPadding => formatter.write_str("padding data is not zero"),
+            Error::IntegerOutOfRange {
+                expected_type,
+                value,
+            } => write!(
+                formatter,
+                "integer {} out of range for {}",
+                value, expected_type
+            ),
+            Error::InvalidBool(v) => {
+                write!(
+                    formatter,
+                    "bool or optional discriminator should be 0 or 1, not {}",
+                    v
+                )
+            }
+            Error::InvalidChar(v) => write!(formatter, "{:#x} is not a valid char", v),
+            Error::UnknownDiscriminant(v) => write!(formatter, "unknown enum discriminant {}", v),
+            Error::LengthOverflow => formatter.write_str("length does not fit in u32"),
+            Error::MapKeyWithoutValue => formatter.write_str("map key without value"),
+            Error::MapValueWithoutKey => formatter.write_str("map value without key"),
+            Error::NotSelfDescribing => {
+                formatter.write_str("XDR is not self-describing, the type to decode must be known")
+            }
+            Error::UnexpectedAuthFlavor(flavor) => {
+                write!(formatter, "unexpected auth flavor {}", flavor)
+            }
+            Error::BufferTooSmall { needed } => {
+                write!(formatter, "buffer too small, {} bytes needed", needed)
+            }
+            Error::InvalidUniversalAddr(uaddr) => {
+                write!(formatter, "invalid universal address {:?}", uaddr)
+            }
+            Error::UnknownAttr(id) => write!(formatter, "unknown fattr4 attribute {}", id),
+            Error::InvalidFragmentLen(len) => write!(
+                formatter,
+                "fragment length {} is not within 1..={}",
+                len,
+                crate::record_marking::MAX_FRAGMENT_LEN
+            ),
+            Error::Io(err) => write!(formatter, "io error: {}", err),
+            Error::LimitExceeded {
+                kind,
+                limit,
+                actual,
+            } => write!(formatter, "{} {} exceeds limit {}", kind, actual, limit),
+            Error::Located {
+                offset,
+                path,
+                error,
+            } => {
+                write!(formatter, "{} at byte {}", error, offset)?;
+                if !path.is_empty() {
+                    write!(formatter, " in {}", path)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Located { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
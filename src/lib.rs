@@ -1,13 +1,41 @@
+#[cfg(feature = "tokio")]
+mod async_io;
 mod de;
 mod error;
 pub mod fixed_length_bytes;
+mod limits;
+#[cfg(feature = "nfs3")]
+pub mod mount3;
+#[cfg(feature = "nfs3")]
+pub mod nfs3;
+#[cfg(feature = "nfs4")]
+pub mod nfs4;
 mod opaque;
+mod optional_list;
+pub mod read;
+pub mod record_marking;
+#[cfg(feature = "rpc")]
+pub mod rpc;
 mod ser;
+mod size;
+mod universal_addr;
 
-pub use de::{XDRDeserializer, from_bytes, deserialize_len};
-pub use error::{Error, Result};
-pub use opaque::FixedLengthBytes;
-pub use ser::{XDRSerializer, to_bytes, serialize_len};
+#[cfg(feature = "tokio")]
+pub use async_io::{
+    DEFAULT_MAX_FRAME_LEN, from_async_reader, from_async_reader_framed,
+    from_async_reader_framed_with_limits, from_async_reader_with_limits, to_async_writer,
+    to_async_writer_framed,
+};
+pub use de::{
+    StreamDeserializer, XDRDeserializer, deserialize_len, from_bytes, from_bytes_prefix,
+    from_bytes_with_limits, from_reader,
+};
+pub use error::{Error, LimitKind, Result};
+pub use limits::DecodeLimits;
+pub use opaque::{FixedLengthBytes, VariableLengthBytes, VariableLengthString};
+pub use optional_list::OptionalList;
+pub use ser::{XDRSerializer, serialize_len, to_bytes, to_bytes_into, to_slice, to_writer};
+pub use universal_addr::UniversalAddr;
 pub use xdr_brk_enum::{XDREnumDeserialize, XDREnumSerialize};
 
 pub(crate) fn padding_len(len: usize) -> usize {
@@ -18,6 +46,12 @@ pub(crate) const PADDING_BYTES: [u8; 3] = [0; 3];
 pub const U32_SIZE: usize = 4;
 pub const U64_SIZE: usize = 8;
 
+/// default nesting limit of `DecodeLimits`, low enough that hostile input
+/// nesting a recursive type such as an `Option<Box<T>>` list fails with an
+/// error long before it could overflow the stack; each element of such a list takes two levels,
+/// so declare long lists as `OptionalList`, which takes one level for any length
+pub const RECOMMENDED_MAX_DEPTH: usize = 256;
+
 #[cfg(test)]
 mod tests {
     use crate::de::from_bytes;
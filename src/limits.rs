@@ -0,0 +1,51 @@
+use crate::RECOMMENDED_MAX_DEPTH;
+use crate::error::{Error, LimitKind, Result};
+
+/// Bounds applied by `XDRDeserializer` to values read from the wire.
+///
+/// `max_depth` defaults to `RECOMMENDED_MAX_DEPTH`, every other limit to `usize::MAX`,
+/// i.e. unlimited; lower them when decoding untrusted input, e.g.
+/// `DecodeLimits { max_seq_len: 1024, ..Default::default() }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// maximum number of elements of a variable-length array or map
+    pub max_seq_len: usize,
+    /// maximum length of a single opaque or string
+    pub max_bytes_len: usize,
+    /// maximum total over one deserializer of opaque and string bytes,
+    /// plus four bytes for each announced array or map element
+    pub max_alloc_bytes: usize,
+    /// maximum nesting of structs, tuples, enums, arrays, maps and optionals
+    pub max_depth: usize,
+}
+
+impl DecodeLimits {
+    /// no limits at all, not even on nesting depth
+    pub const UNLIMITED: DecodeLimits = DecodeLimits {
+        max_seq_len: usize::MAX,
+        max_bytes_len: usize::MAX,
+        max_alloc_bytes: usize::MAX,
+        max_depth: usize::MAX,
+    };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_depth: RECOMMENDED_MAX_DEPTH,
+            ..Self::UNLIMITED
+        }
+    }
+}
+
+pub(crate) fn check_limit(kind: LimitKind, limit: usize, actual: usize) -> Result<()> {
+    if actual > limit {
+        Err(Error::LimitExceeded {
+            kind,
+            limit,
+            actual,
+        })
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+//! MOUNT version 3 protocol types (RFC 1813 appendix I), with an in-process
+//! mock mountd for tests.
+//!
+//! The `mountlist`, `groups` and `exports` linked lists are `OptionalList`s,
+//! so their length is not bounded by the nesting depth limit. Paths and host
+//! names are uninterpreted bytes, not necessarily UTF-8, as in `crate::nfs3`.
+
+use serde::de::{self, SeqAccess};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::nfs3::NfsFh3;
+use crate::rpc::{AuthFlavor, CallBody, RpcServer};
+use crate::{OptionalList, VariableLengthBytes, XDREnumDeserialize, XDREnumSerialize};
+
+pub const MOUNT_PROGRAM: u32 = 100005;
+pub const MOUNT_V3: u32 = 3;
+
+pub const MOUNTPROC3_NULL: u32 = 0;
+pub const MOUNTPROC3_MNT: u32 = 1;
+pub const MOUNTPROC3_DUMP: u32 = 2;
+pub const MOUNTPROC3_UMNT: u32 = 3;
+pub const MOUNTPROC3_UMNTALL: u32 = 4;
+pub const MOUNTPROC3_EXPORT: u32 = 5;
+
+pub const MNTPATHLEN: usize = 1024;
+pub const MNTNAMLEN: usize = 255;
+pub const FHSIZE3: usize = 64;
+
+pub type Dirpath = VariableLengthBytes<MNTPATHLEN>;
+pub type Name = VariableLengthBytes<MNTNAMLEN>;
+/// `opaque<FHSIZE3>`, the handle of the exported directory for NFS calls
+pub type Fhandle3 = NfsFh3;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Mountstat3 {
+    Mnt3Ok = 0,
+    Mnt3errPerm = 1,
+    Mnt3errNoent = 2,
+    Mnt3errIo = 5,
+    Mnt3errAcces = 13,
+    Mnt3errNotdir = 20,
+    Mnt3errInval = 22,
+    Mnt3errNametoolong = 63,
+    Mnt3errNotsupp = 10004,
+    Mnt3errServerfault = 10006,
+    /// status not listed in RFC 1813, kept so replies can still be decoded
+    #[default_arm]
+    Unknown(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mountres3Ok {
+    pub fhandle: Fhandle3,
+    /// flavors the server accepts for NFS calls on this export
+    pub auth_flavors: Vec<AuthFlavor>,
+}
+
+/// `mountres3`, switched on `Mountstat3`; void on failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mountres3 {
+    Ok(Mountres3Ok),
+    /// failure with any status but `Mnt3Ok`
+    Fail(Mountstat3),
+}
+
+impl Mountres3 {
+    pub fn status(&self) -> Mountstat3 {
+        match self {
+            Mountres3::Ok(_) => Mountstat3::Mnt3Ok,
+            Mountres3::Fail(status) => *status,
+        }
+    }
+}
+
+impl Serialize for Mountres3 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Mountres3::Ok(mountinfo) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&Mountstat3::Mnt3Ok)?;
+                tuple.serialize_element(mountinfo)?;
+                tuple.end()
+            }
+            Mountres3::Fail(Mountstat3::Mnt3Ok) => Err(serde::ser::Error::custom(
+                "failed result with status MNT3_OK",
+            )),
+            Mountres3::Fail(status) => status.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Mountres3 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Mountres3;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("mountstat3 followed by mountres3_ok on MNT3_OK")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let status: Mountstat3 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                match status {
+                    Mountstat3::Mnt3Ok => Ok(Mountres3::Ok(
+                        seq.next_element()?
+                            .ok_or_else(|| de::Error::invalid_length(1, &self))?,
+                    )),
+                    status => Ok(Mountres3::Fail(status)),
+                }
+            }
+        }
+
+        deserializer.deserialize_tuple(2, Visitor)
+    }
+}
+
+/// `mountbody` without its `ml_next` link: `ml_hostname` has `ml_directory` mounted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mountbody {
+    pub ml_hostname: Name,
+    pub ml_directory: Dirpath,
+}
+
+pub type Mountlist = OptionalList<Mountbody>;
+
+/// names of the hosts or netgroups an export is restricted to
+pub type Groups = OptionalList<Name>;
+
+/// `exportnode` without its `ex_next` link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exportnode {
+    pub ex_dir: Dirpath,
+    /// empty when the directory is exported to every host
+    pub ex_groups: Groups,
+}
+
+pub type Exports = OptionalList<Exportnode>;
+
+#[derive(Debug)]
+struct Export {
+    node: Exportnode,
+    fhandle: Fhandle3,
+}
+
+#[derive(Debug, Default)]
+struct MountdState {
+    exports: Vec<Export>,
+    mounts: Vec<Mountbody>,
+}
+
+/// In-process mountd for tests.
+///
+/// Mounts any exported directory for any client, without checking
+/// `ex_groups`, and names clients by the machine name of their `AUTH_SYS`
+/// credential, or the empty string without one.
+#[derive(Debug, Clone, Default)]
+pub struct MockMountd {
+    state: Arc<Mutex<MountdState>>,
+    auth_flavors: Vec<AuthFlavor>,
+}
+
+impl MockMountd {
+    /// mountd offering `AUTH_SYS` for NFS calls
+    pub fn new() -> Self {
+        Self::default().with_auth_flavors(vec![AuthFlavor::AuthSys])
+    }
+
+    /// flavors returned by `MNT`
+    pub fn with_auth_flavors(mut self, auth_flavors: Vec<AuthFlavor>) -> Self {
+        self.auth_flavors = auth_flavors;
+        self
+    }
+
+    /// export `dir` to `groups`, or to every host if empty; `MNT` of `dir`
+    /// returns `fhandle`
+    pub fn export(&self, dir: Dirpath, groups: Vec<Name>, fhandle: Fhandle3) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state.exports.retain(|export| export.node.ex_dir != dir);
+        state.exports.push(Export {
+            node: Exportnode {
+                ex_dir: dir,
+                ex_groups: groups.into(),
+            },
+            fhandle,
+        });
+        self
+    }
+
+    /// current mounts, as `DUMP` reports them
+    pub fn mounts(&self) -> Vec<Mountbody> {
+        self.state.lock().unwrap().mounts.clone()
+    }
+
+    fn hostname(call: &CallBody) -> Name {
+        call.auth_sys()
+            .ok()
+            .and_then(|params| params.machinename.into_bytes().try_into().ok())
+            .unwrap_or_default()
+    }
+
+    fn mnt(&self, call: &CallBody, dir: Dirpath) -> Mountres3 {
+        let mut state = self.state.lock().unwrap();
+        let Some(fhandle) = state
+            .exports
+            .iter()
+            .find(|export| export.node.ex_dir == dir)
+            .map(|export| export.fhandle.clone())
+        else {
+            return Mountres3::Fail(Mountstat3::Mnt3errNoent);
+        };
+        let mount = Mountbody {
+            ml_hostname: Self::hostname(call),
+            ml_directory: dir,
+        };
+        if !state.mounts.contains(&mount) {
+            state.mounts.push(mount);
+        }
+        Mountres3::Ok(Mountres3Ok {
+            fhandle,
+            auth_flavors: self.auth_flavors.clone(),
+        })
+    }
+
+    /// register the MOUNT procedures on `server`
+    pub fn register_procedures(&self, server: &mut RpcServer) {
+        let mountd = self.clone();
+        server.register_non_idempotent(
+            MOUNT_PROGRAM,
+            MOUNT_V3,
+            MOUNTPROC3_MNT,
+            move |call, dir: Dirpath| Ok(mountd.mnt(call, dir)),
+        );
+        let mountd = self.clone();
+        server.register(MOUNT_PROGRAM, MOUNT_V3, MOUNTPROC3_DUMP, move |_, ()| {
+            Ok(Mountlist::from(mountd.mounts()))
+        });
+        let mountd = self.clone();
+        server.register(
+            MOUNT_PROGRAM,
+            MOUNT_V3,
+            MOUNTPROC3_UMNT,
+            move |call, dir: Dirpath| {
+                let hostname = Self::hostname(call);
+                let mut state = mountd.state.lock().unwrap();
+                state
+                    .mounts
+                    .retain(|mount| (&mount.ml_hostname, &mount.ml_directory) != (&hostname, &dir));
+                Ok(())
+            },
+        );
+        let mountd = self.clone();
+        server.register(
+            MOUNT_PROGRAM,
+            MOUNT_V3,
+            MOUNTPROC3_UMNTALL,
+            move |call, ()| {
+                let hostname = Self::hostname(call);
+                let mut state = mountd.state.lock().unwrap();
+                state.mounts.retain(|mount| mount.ml_hostname != hostname);
+                Ok(())
+            },
+        );
+        let mountd = self.clone();
+        server.register(MOUNT_PROGRAM, MOUNT_V3, MOUNTPROC3_EXPORT, move |_, ()| {
+            let state = mountd.state.lock().unwrap();
+            Ok(state
+                .exports
+                .iter()
+                .map(|export| export.node.clone())
+                .collect::<Exports>())
+        });
+    }
+
+    /// serve this mountd over TCP on an ephemeral port of 127.0.0.1, from a
+    /// background thread, returning the address to reach it at
+    pub fn spawn_local(&self) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let mut server = RpcServer::new();
+        self.register_procedures(&mut server);
+        thread::spawn(move || server.serve_tcp(&listener));
+        Ok(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{AuthSysParams, OpaqueAuth, RpcClient, RpcError};
+    use crate::{RECOMMENDED_MAX_DEPTH, from_bytes, to_bytes};
+
+    fn opaque<const MAX: usize>(bytes: impl AsRef<[u8]>) -> VariableLengthBytes<MAX> {
+        bytes.as_ref().to_vec().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_mount_wire() {
+        let res = Mountres3::Ok(Mountres3Ok {
+            fhandle: NfsFh3 {
+                data: vec![1, 2, 3, 4].try_into().unwrap(),
+            },
+            auth_flavors: vec![AuthFlavor::AuthSys, AuthFlavor::AuthNone],
+        });
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0, 0, 0, 0, // MNT3_OK
+                0, 0, 0, 4, 1, 2, 3, 4, // fhandle
+                0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0, // auth_flavors
+            ]
+        );
+        assert_eq!(from_bytes::<Mountres3>(&bytes).unwrap(), res);
+
+        let res = Mountres3::Fail(Mountstat3::Mnt3errAcces);
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 13]);
+        assert_eq!(from_bytes::<Mountres3>(&bytes).unwrap(), res);
+        assert!(to_bytes(&Mountres3::Fail(Mountstat3::Mnt3Ok)).is_err());
+
+        let exports: Exports = vec![Exportnode {
+            ex_dir: opaque("/a"),
+            ex_groups: vec![opaque("h")].into(),
+        }]
+        .into();
+        let bytes = to_bytes(&exports).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0, 0, 0, 1, // exportnode follows
+                0, 0, 0, 2, b'/', b'a', 0, 0, // ex_dir
+                0, 0, 0, 1, 0, 0, 0, 1, b'h', 0, 0, 0, // one group
+                0, 0, 0, 0, // end of groups
+                0, 0, 0, 0, // end of exports
+            ]
+        );
+        assert_eq!(from_bytes::<Exports>(&bytes).unwrap(), exports);
+        let name = to_bytes(&"h".repeat(MNTNAMLEN + 1)).unwrap();
+        assert!(from_bytes::<Name>(&name).is_err());
+        // a path in Latin-1, which is not UTF-8, passes through unchanged
+        let dir: Dirpath = opaque(b"/caf\xe9");
+        assert_eq!(
+            from_bytes::<Dirpath>(&to_bytes(&dir).unwrap()).unwrap(),
+            dir
+        );
+
+        // longer than a recursive mountlist could be within the depth limit
+        let mounts: Mountlist = (0..RECOMMENDED_MAX_DEPTH)
+            .map(|i| Mountbody {
+                ml_hostname: opaque(format!("client{}", i)),
+                ml_directory: opaque("/export"),
+            })
+            .collect();
+        assert_eq!(
+            from_bytes::<Mountlist>(&to_bytes(&mounts).unwrap()).unwrap(),
+            mounts
+        );
+    }
+
+    #[test]
+    fn test_mock_mountd() {
+        let fhandle = NfsFh3 {
+            data: vec![0xfe; 16].try_into().unwrap(),
+        };
+        let mountd = MockMountd::new();
+        mountd.export(opaque("/export"), Vec::new(), fhandle.clone());
+        mountd.export(
+            opaque("/private"),
+            vec![opaque("trusted")],
+            NfsFh3::default(),
+        );
+        let addr = mountd.spawn_local().unwrap();
+
+        let params = AuthSysParams {
+            machinename: "client".to_string(),
+            ..Default::default()
+        };
+        let cred = OpaqueAuth::try_from(&params).unwrap();
+        let client = RpcClient::connect(addr, MOUNT_PROGRAM, MOUNT_V3)
+            .unwrap()
+            .with_auth(cred, OpaqueAuth::none());
+
+        let exports: Exports = client.call(MOUNTPROC3_EXPORT, &()).unwrap();
+        assert_eq!(exports.len(), 2);
+        assert_eq!(&*exports[1].ex_groups[0], b"trusted");
+
+        let res: Mountres3 = client.call(MOUNTPROC3_MNT, "/export").unwrap();
+        assert_eq!(
+            res,
+            Mountres3::Ok(Mountres3Ok {
+                fhandle,
+                auth_flavors: vec![AuthFlavor::AuthSys],
+            })
+        );
+        let res: Mountres3 = client.call(MOUNTPROC3_MNT, "/missing").unwrap();
+        assert_eq!(res.status(), Mountstat3::Mnt3errNoent);
+        let res: Mountres3 = client.call(MOUNTPROC3_MNT, "/private").unwrap();
+        assert_eq!(res.status(), Mountstat3::Mnt3Ok);
+        // a dirpath over MNTPATHLEN does not decode
+        let res = client.call::<_, Mountres3>(MOUNTPROC3_MNT, &"/".repeat(MNTPATHLEN + 1));
+        assert!(matches!(res, Err(RpcError::GarbageArgs)));
+
+        let mounts: Mountlist = client.call(MOUNTPROC3_DUMP, &()).unwrap();
+        assert_eq!(
+            mounts[..],
+            [
+                Mountbody {
+                    ml_hostname: opaque("client"),
+                    ml_directory: opaque("/export"),
+                },
+                Mountbody {
+                    ml_hostname: opaque("client"),
+                    ml_directory: opaque("/private"),
+                },
+            ]
+        );
+
+        let () = client.call(MOUNTPROC3_UMNT, "/export").unwrap();
+        assert_eq!(mountd.mounts().len(), 1);
+        let () = client.call(MOUNTPROC3_UMNTALL, &()).unwrap();
+        assert!(mountd.mounts().is_empty());
+    }
+}
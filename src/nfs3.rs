@@ -0,0 +1,1080 @@
+//! NFS version 3 protocol types (RFC 1813).
+//!
+//! Type names follow the RFC in upper camel case, such as `Fattr3` for
+//! `fattr3` and `Read3ResOk` for `READ3resok`; field names are kept. Optional
+//! data (`post_op_attr`, `set_mode3`, ...) are `Option`s, directory listings
+//! are `OptionalList`s, and every `*3res` union is an `Nfs3Result` of its
+//! `resok` and `resfail` arms. File names and paths are uninterpreted bytes,
+//! not necessarily UTF-8. Call and reply headers are in `crate::rpc`.
+
+use serde::de::{self, SeqAccess};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::{
+    FixedLengthBytes, OptionalList, VariableLengthBytes, XDREnumDeserialize, XDREnumSerialize,
+};
+
+pub const NFS_PROGRAM: u32 = 100003;
+pub const NFS_V3: u32 = 3;
+
+pub const NFSPROC3_NULL: u32 = 0;
+pub const NFSPROC3_GETATTR: u32 = 1;
+pub const NFSPROC3_SETATTR: u32 = 2;
+pub const NFSPROC3_LOOKUP: u32 = 3;
+pub const NFSPROC3_ACCESS: u32 = 4;
+pub const NFSPROC3_READLINK: u32 = 5;
+pub const NFSPROC3_READ: u32 = 6;
+pub const NFSPROC3_WRITE: u32 = 7;
+pub const NFSPROC3_CREATE: u32 = 8;
+pub const NFSPROC3_MKDIR: u32 = 9;
+pub const NFSPROC3_SYMLINK: u32 = 10;
+pub const NFSPROC3_MKNOD: u32 = 11;
+pub const NFSPROC3_REMOVE: u32 = 12;
+pub const NFSPROC3_RMDIR: u32 = 13;
+pub const NFSPROC3_RENAME: u32 = 14;
+pub const NFSPROC3_LINK: u32 = 15;
+pub const NFSPROC3_READDIR: u32 = 16;
+pub const NFSPROC3_READDIRPLUS: u32 = 17;
+pub const NFSPROC3_FSSTAT: u32 = 18;
+pub const NFSPROC3_FSINFO: u32 = 19;
+pub const NFSPROC3_PATHCONF: u32 = 20;
+pub const NFSPROC3_COMMIT: u32 = 21;
+
+pub const NFS3_FHSIZE: usize = 64;
+pub const NFS3_COOKIEVERFSIZE: usize = 8;
+pub const NFS3_CREATEVERFSIZE: usize = 8;
+pub const NFS3_WRITEVERFSIZE: usize = 8;
+
+/// `Access3Args::access` and `Access3ResOk::access` bits
+pub const ACCESS3_READ: u32 = 0x0001;
+pub const ACCESS3_LOOKUP: u32 = 0x0002;
+pub const ACCESS3_MODIFY: u32 = 0x0004;
+pub const ACCESS3_EXTEND: u32 = 0x0008;
+pub const ACCESS3_DELETE: u32 = 0x0010;
+pub const ACCESS3_EXECUTE: u32 = 0x0020;
+
+/// `Fsinfo3ResOk::properties` bits
+pub const FSF3_LINK: u32 = 0x0001;
+pub const FSF3_SYMLINK: u32 = 0x0002;
+pub const FSF3_HOMOGENEOUS: u32 = 0x0008;
+pub const FSF3_CANSETTIME: u32 = 0x0010;
+
+pub type Filename3 = VariableLengthBytes;
+pub type Nfspath3 = VariableLengthBytes;
+pub type Fileid3 = u64;
+pub type Cookie3 = u64;
+pub type Cookieverf3 = FixedLengthBytes<NFS3_COOKIEVERFSIZE>;
+pub type Createverf3 = FixedLengthBytes<NFS3_CREATEVERFSIZE>;
+pub type Writeverf3 = FixedLengthBytes<NFS3_WRITEVERFSIZE>;
+pub type Uid3 = u32;
+pub type Gid3 = u32;
+pub type Size3 = u64;
+pub type Offset3 = u64;
+pub type Mode3 = u32;
+pub type Count3 = u32;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Nfsstat3 {
+    Nfs3Ok = 0,
+    Nfs3errPerm = 1,
+    Nfs3errNoent = 2,
+    Nfs3errIo = 5,
+    Nfs3errNxio = 6,
+    Nfs3errAcces = 13,
+    Nfs3errExist = 17,
+    Nfs3errXdev = 18,
+    Nfs3errNodev = 19,
+    Nfs3errNotdir = 20,
+    Nfs3errIsdir = 21,
+    Nfs3errInval = 22,
+    Nfs3errFbig = 27,
+    Nfs3errNospc = 28,
+    Nfs3errRofs = 30,
+    Nfs3errMlink = 31,
+    Nfs3errNametoolong = 63,
+    Nfs3errNotempty = 66,
+    Nfs3errDquot = 69,
+    Nfs3errStale = 70,
+    Nfs3errRemote = 71,
+    Nfs3errBadhandle = 10001,
+    Nfs3errNotSync = 10002,
+    Nfs3errBadCookie = 10003,
+    Nfs3errNotsupp = 10004,
+    Nfs3errToosmall = 10005,
+    Nfs3errServerfault = 10006,
+    Nfs3errBadtype = 10007,
+    Nfs3errJukebox = 10008,
+    /// status not listed in RFC 1813, kept so replies can still be decoded
+    #[default_arm]
+    Unknown(u32),
+}
+
+/// `*3res` union switched on `Nfsstat3`: `resok` on `Nfs3Ok`, `resfail`
+/// otherwise. `F` is `()` for the unions whose failure arm is void.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nfs3Result<T, F> {
+    Ok(T),
+    /// failure with any status but `Nfs3Ok`
+    Fail(Nfsstat3, F),
+}
+
+impl<T, F> Nfs3Result<T, F> {
+    pub fn status(&self) -> Nfsstat3 {
+        match self {
+            Nfs3Result::Ok(_) => Nfsstat3::Nfs3Ok,
+            Nfs3Result::Fail(status, _) => *status,
+        }
+    }
+
+    pub fn into_result(self) -> Result<T, (Nfsstat3, F)> {
+        match self {
+            Nfs3Result::Ok(resok) => Ok(resok),
+            Nfs3Result::Fail(status, resfail) => Err((status, resfail)),
+        }
+    }
+}
+
+impl<T: Serialize, F: Serialize> Serialize for Nfs3Result<T, F> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        match self {
+            Nfs3Result::Ok(resok) => {
+                tuple.serialize_element(&Nfsstat3::Nfs3Ok)?;
+                tuple.serialize_element(resok)?;
+            }
+            Nfs3Result::Fail(Nfsstat3::Nfs3Ok, _) => {
+                return Err(serde::ser::Error::custom(
+                    "failed result with status NFS3_OK",
+                ));
+            }
+            Nfs3Result::Fail(status, resfail) => {
+                tuple.serialize_element(status)?;
+                tuple.serialize_element(resfail)?;
+            }
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: Deserialize<'de>> Deserialize<'de> for Nfs3Result<T, F> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor<T, F>(PhantomData<(T, F)>);
+
+        impl<'de, T: Deserialize<'de>, F: Deserialize<'de>> de::Visitor<'de> for Visitor<T, F> {
+            type Value = Nfs3Result<T, F>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("nfsstat3 followed by resok or resfail")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let status: Nfsstat3 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let missing = || de::Error::invalid_length(1, &self);
+                match status {
+                    Nfsstat3::Nfs3Ok => {
+                        Ok(Nfs3Result::Ok(seq.next_element()?.ok_or_else(missing)?))
+                    }
+                    status => Ok(Nfs3Result::Fail(
+                        status,
+                        seq.next_element()?.ok_or_else(missing)?,
+                    )),
+                }
+            }
+        }
+
+        deserializer.deserialize_tuple(2, Visitor(PhantomData))
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Ftype3 {
+    Nf3reg = 1,
+    Nf3dir = 2,
+    Nf3blk = 3,
+    Nf3chr = 4,
+    Nf3lnk = 5,
+    Nf3sock = 6,
+    Nf3fifo = 7,
+}
+
+/// major and minor device numbers of a block or character device
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Specdata3 {
+    pub specdata1: u32,
+    pub specdata2: u32,
+}
+
+/// File handle, opaque to the client.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NfsFh3 {
+    pub data: VariableLengthBytes<NFS3_FHSIZE>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nfstime3 {
+    pub seconds: u32,
+    pub nseconds: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fattr3 {
+    pub r#type: Ftype3,
+    pub mode: Mode3,
+    pub nlink: u32,
+    pub uid: Uid3,
+    pub gid: Gid3,
+    pub size: Size3,
+    pub used: Size3,
+    pub rdev: Specdata3,
+    pub fsid: u64,
+    pub fileid: Fileid3,
+    pub atime: Nfstime3,
+    pub mtime: Nfstime3,
+    pub ctime: Nfstime3,
+}
+
+pub type PostOpAttr = Option<Fattr3>;
+
+/// attributes kept for weak cache consistency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WccAttr {
+    pub size: Size3,
+    pub mtime: Nfstime3,
+    pub ctime: Nfstime3,
+}
+
+pub type PreOpAttr = Option<WccAttr>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WccData {
+    pub before: PreOpAttr,
+    pub after: PostOpAttr,
+}
+
+pub type PostOpFh3 = Option<NfsFh3>;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum TimeHow {
+    DontChange = 0,
+    SetToServerTime = 1,
+    SetToClientTime = 2,
+}
+
+pub type SetMode3 = Option<Mode3>;
+pub type SetUid3 = Option<Uid3>;
+pub type SetGid3 = Option<Gid3>;
+pub type SetSize3 = Option<Size3>;
+
+/// `set_atime` and `set_mtime`, switched on `TimeHow`
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum SetTime {
+    #[default]
+    DontChange = 0,
+    SetToServerTime = 1,
+    SetToClientTime(Nfstime3) = 2,
+}
+
+pub type SetAtime = SetTime;
+pub type SetMtime = SetTime;
+
+/// Attributes to set, the default changing none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sattr3 {
+    pub mode: SetMode3,
+    pub uid: SetUid3,
+    pub gid: SetGid3,
+    pub size: SetSize3,
+    pub atime: SetAtime,
+    pub mtime: SetMtime,
+}
+
+/// name `name` in directory `dir`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diropargs3 {
+    pub dir: NfsFh3,
+    pub name: Filename3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Getattr3Args {
+    pub object: NfsFh3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Getattr3ResOk {
+    pub obj_attributes: Fattr3,
+}
+
+pub type Getattr3Res = Nfs3Result<Getattr3ResOk, ()>;
+
+/// `ctime` the object must still have for `SETATTR` to proceed
+pub type Sattrguard3 = Option<Nfstime3>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setattr3Args {
+    pub object: NfsFh3,
+    pub new_attributes: Sattr3,
+    pub guard: Sattrguard3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setattr3ResOk {
+    pub obj_wcc: WccData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setattr3ResFail {
+    pub obj_wcc: WccData,
+}
+
+pub type Setattr3Res = Nfs3Result<Setattr3ResOk, Setattr3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lookup3Args {
+    pub what: Diropargs3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lookup3ResOk {
+    pub object: NfsFh3,
+    pub obj_attributes: PostOpAttr,
+    pub dir_attributes: PostOpAttr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lookup3ResFail {
+    pub dir_attributes: PostOpAttr,
+}
+
+pub type Lookup3Res = Nfs3Result<Lookup3ResOk, Lookup3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access3Args {
+    pub object: NfsFh3,
+    pub access: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access3ResOk {
+    pub obj_attributes: PostOpAttr,
+    pub access: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access3ResFail {
+    pub obj_attributes: PostOpAttr,
+}
+
+pub type Access3Res = Nfs3Result<Access3ResOk, Access3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readlink3Args {
+    pub symlink: NfsFh3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readlink3ResOk {
+    pub symlink_attributes: PostOpAttr,
+    pub data: Nfspath3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readlink3ResFail {
+    pub symlink_attributes: PostOpAttr,
+}
+
+pub type Readlink3Res = Nfs3Result<Readlink3ResOk, Readlink3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Read3Args {
+    pub file: NfsFh3,
+    pub offset: Offset3,
+    pub count: Count3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Read3ResOk {
+    pub file_attributes: PostOpAttr,
+    pub count: Count3,
+    pub eof: bool,
+    pub data: VariableLengthBytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Read3ResFail {
+    pub file_attributes: PostOpAttr,
+}
+
+pub type Read3Res = Nfs3Result<Read3ResOk, Read3ResFail>;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum StableHow {
+    Unstable = 0,
+    DataSync = 1,
+    FileSync = 2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Write3Args {
+    pub file: NfsFh3,
+    pub offset: Offset3,
+    pub count: Count3,
+    pub stable: StableHow,
+    pub data: VariableLengthBytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Write3ResOk {
+    pub file_wcc: WccData,
+    pub count: Count3,
+    pub committed: StableHow,
+    pub verf: Writeverf3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Write3ResFail {
+    pub file_wcc: WccData,
+}
+
+pub type Write3Res = Nfs3Result<Write3ResOk, Write3ResFail>;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Createmode3 {
+    Unchecked = 0,
+    Guarded = 1,
+    Exclusive = 2,
+}
+
+/// `createhow3`, switched on `Createmode3`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Createhow3 {
+    Unchecked(Sattr3) = 0,
+    Guarded(Sattr3) = 1,
+    Exclusive(Createverf3) = 2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Create3Args {
+    /// `where` in the RFC
+    pub where_: Diropargs3,
+    pub how: Createhow3,
+}
+
+/// `resok` of `CREATE`, `MKDIR`, `SYMLINK` and `MKNOD`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Create3ResOk {
+    pub obj: PostOpFh3,
+    pub obj_attributes: PostOpAttr,
+    pub dir_wcc: WccData,
+}
+
+/// `resfail` of `CREATE`, `MKDIR`, `SYMLINK` and `MKNOD`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Create3ResFail {
+    pub dir_wcc: WccData,
+}
+
+pub type Create3Res = Nfs3Result<Create3ResOk, Create3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mkdir3Args {
+    pub where_: Diropargs3,
+    pub attributes: Sattr3,
+}
+
+pub type Mkdir3ResOk = Create3ResOk;
+pub type Mkdir3ResFail = Create3ResFail;
+pub type Mkdir3Res = Create3Res;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symlinkdata3 {
+    pub symlink_attributes: Sattr3,
+    pub symlink_data: Nfspath3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symlink3Args {
+    pub where_: Diropargs3,
+    pub symlink: Symlinkdata3,
+}
+
+pub type Symlink3ResOk = Create3ResOk;
+pub type Symlink3ResFail = Create3ResFail;
+pub type Symlink3Res = Create3Res;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Devicedata3 {
+    pub dev_attributes: Sattr3,
+    pub spec: Specdata3,
+}
+
+/// `mknoddata3`, switched on `Ftype3`; void for the types `MKNOD` does not create
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Mknoddata3 {
+    Nf3reg = 1,
+    Nf3dir = 2,
+    Nf3blk(Devicedata3) = 3,
+    Nf3chr(Devicedata3) = 4,
+    Nf3lnk = 5,
+    Nf3sock(Sattr3) = 6,
+    Nf3fifo(Sattr3) = 7,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mknod3Args {
+    pub where_: Diropargs3,
+    pub what: Mknoddata3,
+}
+
+pub type Mknod3ResOk = Create3ResOk;
+pub type Mknod3ResFail = Create3ResFail;
+pub type Mknod3Res = Create3Res;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remove3Args {
+    pub object: Diropargs3,
+}
+
+/// `resok` and `resfail` of `REMOVE` and `RMDIR`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remove3ResOk {
+    pub dir_wcc: WccData,
+}
+
+pub type Remove3ResFail = Remove3ResOk;
+pub type Remove3Res = Nfs3Result<Remove3ResOk, Remove3ResFail>;
+
+pub type Rmdir3Args = Remove3Args;
+pub type Rmdir3ResOk = Remove3ResOk;
+pub type Rmdir3ResFail = Remove3ResOk;
+pub type Rmdir3Res = Remove3Res;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename3Args {
+    pub from: Diropargs3,
+    pub to: Diropargs3,
+}
+
+/// `resok` and `resfail` of `RENAME`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename3ResOk {
+    pub fromdir_wcc: WccData,
+    pub todir_wcc: WccData,
+}
+
+pub type Rename3ResFail = Rename3ResOk;
+pub type Rename3Res = Nfs3Result<Rename3ResOk, Rename3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link3Args {
+    pub file: NfsFh3,
+    pub link: Diropargs3,
+}
+
+/// `resok` and `resfail` of `LINK`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link3ResOk {
+    pub file_attributes: PostOpAttr,
+    pub linkdir_wcc: WccData,
+}
+
+pub type Link3ResFail = Link3ResOk;
+pub type Link3Res = Nfs3Result<Link3ResOk, Link3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdir3Args {
+    pub dir: NfsFh3,
+    pub cookie: Cookie3,
+    pub cookieverf: Cookieverf3,
+    pub count: Count3,
+}
+
+/// `entry3` without its `nextentry` link, see `Dirlist3`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry3 {
+    pub fileid: Fileid3,
+    pub name: Filename3,
+    pub cookie: Cookie3,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dirlist3 {
+    pub entries: OptionalList<Entry3>,
+    pub eof: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdir3ResOk {
+    pub dir_attributes: PostOpAttr,
+    pub cookieverf: Cookieverf3,
+    pub reply: Dirlist3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdir3ResFail {
+    pub dir_attributes: PostOpAttr,
+}
+
+pub type Readdir3Res = Nfs3Result<Readdir3ResOk, Readdir3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdirplus3Args {
+    pub dir: NfsFh3,
+    pub cookie: Cookie3,
+    pub cookieverf: Cookieverf3,
+    pub dircount: Count3,
+    pub maxcount: Count3,
+}
+
+/// `entryplus3` without its `nextentry` link, see `Dirlistplus3`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entryplus3 {
+    pub fileid: Fileid3,
+    pub name: Filename3,
+    pub cookie: Cookie3,
+    pub name_attributes: PostOpAttr,
+    pub name_handle: PostOpFh3,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dirlistplus3 {
+    pub entries: OptionalList<Entryplus3>,
+    pub eof: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdirplus3ResOk {
+    pub dir_attributes: PostOpAttr,
+    pub cookieverf: Cookieverf3,
+    pub reply: Dirlistplus3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdirplus3ResFail {
+    pub dir_attributes: PostOpAttr,
+}
+
+pub type Readdirplus3Res = Nfs3Result<Readdirplus3ResOk, Readdirplus3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsstat3Args {
+    pub fsroot: NfsFh3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsstat3ResOk {
+    pub obj_attributes: PostOpAttr,
+    pub tbytes: Size3,
+    pub fbytes: Size3,
+    pub abytes: Size3,
+    pub tfiles: Size3,
+    pub ffiles: Size3,
+    pub afiles: Size3,
+    pub invarsec: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsstat3ResFail {
+    pub obj_attributes: PostOpAttr,
+}
+
+pub type Fsstat3Res = Nfs3Result<Fsstat3ResOk, Fsstat3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsinfo3Args {
+    pub fsroot: NfsFh3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsinfo3ResOk {
+    pub obj_attributes: PostOpAttr,
+    pub rtmax: u32,
+    pub rtpref: u32,
+    pub rtmult: u32,
+    pub wtmax: u32,
+    pub wtpref: u32,
+    pub wtmult: u32,
+    pub dtpref: u32,
+    pub maxfilesize: Size3,
+    pub time_delta: Nfstime3,
+    /// `FSF3_*` bits
+    pub properties: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsinfo3ResFail {
+    pub obj_attributes: PostOpAttr,
+}
+
+pub type Fsinfo3Res = Nfs3Result<Fsinfo3ResOk, Fsinfo3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pathconf3Args {
+    pub object: NfsFh3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pathconf3ResOk {
+    pub obj_attributes: PostOpAttr,
+    pub linkmax: u32,
+    pub name_max: u32,
+    pub no_trunc: bool,
+    pub chown_restricted: bool,
+    pub case_insensitive: bool,
+    pub case_preserving: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pathconf3ResFail {
+    pub obj_attributes: PostOpAttr,
+}
+
+pub type Pathconf3Res = Nfs3Result<Pathconf3ResOk, Pathconf3ResFail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit3Args {
+    pub file: NfsFh3,
+    pub offset: Offset3,
+    pub count: Count3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit3ResOk {
+    pub file_wcc: WccData,
+    pub verf: Writeverf3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit3ResFail {
+    pub file_wcc: WccData,
+}
+
+pub type Commit3Res = Nfs3Result<Commit3ResOk, Commit3ResFail>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, from_bytes, to_bytes};
+
+    fn fh(data: &[u8]) -> NfsFh3 {
+        NfsFh3 {
+            data: data.to_vec().try_into().unwrap(),
+        }
+    }
+
+    fn name(name: &[u8]) -> Filename3 {
+        name.to_vec().try_into().unwrap()
+    }
+
+    fn file_attributes() -> Fattr3 {
+        Fattr3 {
+            r#type: Ftype3::Nf3reg,
+            mode: 0o644,
+            nlink: 1,
+            uid: 1000,
+            gid: 1000,
+            size: 5,
+            used: 4096,
+            rdev: Specdata3::default(),
+            fsid: 0x1_0000_0002,
+            fileid: 42,
+            atime: Nfstime3 {
+                seconds: 1,
+                nseconds: 2,
+            },
+            mtime: Nfstime3 {
+                seconds: 3,
+                nseconds: 4,
+            },
+            ctime: Nfstime3 {
+                seconds: 5,
+                nseconds: 6,
+            },
+        }
+    }
+
+    #[test]
+    fn test_getattr() {
+        let args = Getattr3Args {
+            object: fh(&[1, 2, 3, 4, 5]),
+        };
+        let bytes = to_bytes(&args).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 5, 1, 2, 3, 4, 5, 0, 0, 0]);
+        assert_eq!(from_bytes::<Getattr3Args>(&bytes).unwrap(), args);
+
+        let res = Getattr3Res::Ok(Getattr3ResOk {
+            obj_attributes: file_attributes(),
+        });
+        let bytes = to_bytes(&res).unwrap();
+        // status, then the 84 bytes of fattr3
+        assert_eq!(bytes.len(), 4 + 84);
+        assert_eq!(
+            bytes[..24],
+            [
+                0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0x01, 0xa4, 0, 0, 0, 1, 0, 0, 0x03, 0xe8, 0, 0, 0x03,
+                0xe8
+            ]
+        );
+        assert_eq!(bytes[48..56], [0, 0, 0, 1, 0, 0, 0, 2]);
+        assert_eq!(from_bytes::<Getattr3Res>(&bytes).unwrap(), res);
+
+        // the failure arm of GETATTR3res is void
+        let res = Getattr3Res::Fail(Nfsstat3::Nfs3errStale, ());
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 70]);
+        assert_eq!(from_bytes::<Getattr3Res>(&bytes).unwrap(), res);
+        assert_eq!(res.status(), Nfsstat3::Nfs3errStale);
+
+        let res = Getattr3Res::Fail(Nfsstat3::Unknown(12345), ());
+        assert_eq!(
+            from_bytes::<Getattr3Res>(&to_bytes(&res).unwrap()).unwrap(),
+            res
+        );
+
+        let res = Getattr3Res::Fail(Nfsstat3::Nfs3Ok, ());
+        assert!(matches!(to_bytes(&res), Err(Error::Message(_))));
+    }
+
+    #[test]
+    fn test_lookup() {
+        let args = Lookup3Args {
+            what: Diropargs3 {
+                dir: fh(&[9; 8]),
+                name: name(b"file.txt"),
+            },
+        };
+        assert_eq!(
+            from_bytes::<Lookup3Args>(&to_bytes(&args).unwrap()).unwrap(),
+            args
+        );
+
+        let res = Lookup3Res::Ok(Lookup3ResOk {
+            object: fh(&[7; 32]),
+            obj_attributes: Some(file_attributes()),
+            dir_attributes: None,
+        });
+        assert_eq!(
+            from_bytes::<Lookup3Res>(&to_bytes(&res).unwrap()).unwrap(),
+            res
+        );
+
+        let res = Lookup3Res::Fail(
+            Nfsstat3::Nfs3errNoent,
+            Lookup3ResFail {
+                dir_attributes: None,
+            },
+        );
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 2, 0, 0, 0, 0]);
+        assert_eq!(from_bytes::<Lookup3Res>(&bytes).unwrap(), res);
+        assert_eq!(res.into_result().unwrap_err().0, Nfsstat3::Nfs3errNoent);
+
+        // handles are at most NFS3_FHSIZE bytes
+        let mut bytes = vec![0, 0, 0, 65];
+        bytes.extend([0; 68]);
+        assert!(from_bytes::<Getattr3Args>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_setattr_and_create() {
+        let args = Setattr3Args {
+            object: fh(&[1]),
+            new_attributes: Sattr3 {
+                mode: Some(0o755),
+                atime: SetTime::SetToServerTime,
+                mtime: SetTime::SetToClientTime(Nfstime3 {
+                    seconds: 10,
+                    nseconds: 0,
+                }),
+                ..Default::default()
+            },
+            guard: None,
+        };
+        let bytes = to_bytes(&args).unwrap();
+        assert_eq!(
+            bytes[8..],
+            [
+                0, 0, 0, 1, 0, 0, 0x01, 0xed, // mode
+                0, 0, 0, 0, // uid
+                0, 0, 0, 0, // gid
+                0, 0, 0, 0, // size
+                0, 0, 0, 1, // atime
+                0, 0, 0, 2, 0, 0, 0, 10, 0, 0, 0, 0, // mtime
+                0, 0, 0, 0, // guard
+            ]
+        );
+        assert_eq!(from_bytes::<Setattr3Args>(&bytes).unwrap(), args);
+
+        let args = Create3Args {
+            where_: Diropargs3 {
+                dir: fh(&[1]),
+                name: name(b"new"),
+            },
+            how: Createhow3::Exclusive(FixedLengthBytes::new(*b"verifier")),
+        };
+        let bytes = to_bytes(&args).unwrap();
+        assert_eq!(bytes[16..], *b"\0\0\0\x02verifier");
+        assert_eq!(from_bytes::<Create3Args>(&bytes).unwrap(), args);
+
+        let args = Mknod3Args {
+            where_: Diropargs3 {
+                dir: fh(&[1]),
+                name: name(b"null"),
+            },
+            what: Mknoddata3::Nf3chr(Devicedata3 {
+                dev_attributes: Sattr3::default(),
+                spec: Specdata3 {
+                    specdata1: 1,
+                    specdata2: 3,
+                },
+            }),
+        };
+        assert_eq!(
+            from_bytes::<Mknod3Args>(&to_bytes(&args).unwrap()).unwrap(),
+            args
+        );
+        let args = Mknod3Args {
+            what: Mknoddata3::Nf3reg,
+            ..args
+        };
+        assert_eq!(to_bytes(&args).unwrap()[16..], [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_read_and_write() {
+        let res = Read3Res::Ok(Read3ResOk {
+            file_attributes: None,
+            count: 5,
+            eof: true,
+            data: b"hello".to_vec().try_into().unwrap(),
+        });
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(
+            bytes,
+            *b"\0\0\0\0\0\0\0\0\0\0\0\x05\0\0\0\x01\0\0\0\x05hello\0\0\0"
+        );
+        assert_eq!(from_bytes::<Read3Res>(&bytes).unwrap(), res);
+
+        let res = Write3Res::Ok(Write3ResOk {
+            file_wcc: WccData {
+                before: Some(WccAttr::default()),
+                after: Some(file_attributes()),
+            },
+            count: 5,
+            committed: StableHow::FileSync,
+            verf: FixedLengthBytes::new([1; 8]),
+        });
+        assert_eq!(
+            from_bytes::<Write3Res>(&to_bytes(&res).unwrap()).unwrap(),
+            res
+        );
+    }
+
+    #[test]
+    fn test_readdirplus() {
+        let entry = |fileid: u64, entry_name: &[u8]| Entryplus3 {
+            fileid,
+            name: name(entry_name),
+            cookie: fileid,
+            name_attributes: Some(file_attributes()),
+            name_handle: Some(fh(&fileid.to_be_bytes())),
+        };
+        let res = Readdirplus3Res::Ok(Readdirplus3ResOk {
+            dir_attributes: None,
+            cookieverf: FixedLengthBytes::new([0; 8]),
+            reply: Dirlistplus3 {
+                // names in Latin-1, which are not UTF-8, pass through unchanged
+                entries: vec![entry(1, b"."), entry(2, b".."), entry(3, b"caf\xe9")].into(),
+                eof: true,
+            },
+        });
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(from_bytes::<Readdirplus3Res>(&bytes).unwrap(), res);
+
+        let res = Readdir3Res::Ok(Readdir3ResOk {
+            dir_attributes: None,
+            cookieverf: FixedLengthBytes::new([0; 8]),
+            reply: Dirlist3 {
+                entries: vec![Entry3 {
+                    fileid: 3,
+                    name: name(b"a"),
+                    cookie: 7,
+                }]
+                .into(),
+                eof: false,
+            },
+        });
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(
+            bytes[16..],
+            [
+                0, 0, 0, 1, // entry follows
+                0, 0, 0, 0, 0, 0, 0, 3, // fileid
+                0, 0, 0, 1, b'a', 0, 0, 0, // name
+                0, 0, 0, 0, 0, 0, 0, 7, // cookie
+                0, 0, 0, 0, // no next entry
+                0, 0, 0, 0, // eof
+            ]
+        );
+        assert_eq!(from_bytes::<Readdir3Res>(&bytes).unwrap(), res);
+    }
+
+    #[test]
+    fn test_fsinfo_and_pathconf() {
+        let res = Fsinfo3Res::Ok(Fsinfo3ResOk {
+            obj_attributes: None,
+            rtmax: 1 << 20,
+            rtpref: 1 << 20,
+            rtmult: 4096,
+            wtmax: 1 << 20,
+            wtpref: 1 << 20,
+            wtmult: 4096,
+            dtpref: 1 << 16,
+            maxfilesize: u64::MAX,
+            time_delta: Nfstime3 {
+                seconds: 0,
+                nseconds: 1,
+            },
+            properties: FSF3_LINK | FSF3_SYMLINK | FSF3_HOMOGENEOUS | FSF3_CANSETTIME,
+        });
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes.len(), 4 + 4 + 7 * 4 + 8 + 8 + 4);
+        assert_eq!(from_bytes::<Fsinfo3Res>(&bytes).unwrap(), res);
+
+        let res = Pathconf3Res::Ok(Pathconf3ResOk {
+            obj_attributes: None,
+            linkmax: 32000,
+            name_max: 255,
+            no_trunc: true,
+            chown_restricted: true,
+            case_insensitive: false,
+            case_preserving: true,
+        });
+        assert_eq!(
+            from_bytes::<Pathconf3Res>(&to_bytes(&res).unwrap()).unwrap(),
+            res
+        );
+    }
+}
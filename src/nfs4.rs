@@ -0,0 +1,2122 @@
+//! NFS version 4 protocol types (RFC 7530 and RFC 8881).
+//!
+//! Type names follow the RFCs in upper camel case, such as `Stateid4` for
+//! `stateid4` and `Read4ResOk` for `READ4resok`. `Compound4Args` and
+//! `Compound4Res` carry every NFSv4.0 operation and the NFSv4.1 session and
+//! state management ones; the pNFS layout, device, delegation wanting and
+//! SSV operations are not modeled, so a compound holding one fails to decode.
+//! Results that are a bare status are `Nfsstat4`, and those whose failure arm
+//! is void are `Nfs4Result`s.
+//!
+//! `Fattr4::new` and `Fattr4::values` convert between `AttrValue`s and the
+//! bitmap and `attr_vals` of the wire, in bitmap order.
+
+use serde::de::{self, SeqAccess};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+
+use crate::rpc::AuthSysParams;
+use crate::{
+    Error, FixedLengthBytes, OptionalList, Result, UniversalAddr, VariableLengthBytes,
+    XDREnumDeserialize, XDREnumSerialize, from_bytes_prefix, to_bytes_into,
+};
+
+pub const NFS4_PROGRAM: u32 = 100003;
+pub const NFS_V4: u32 = 4;
+
+pub const NFSPROC4_NULL: u32 = 0;
+pub const NFSPROC4_COMPOUND: u32 = 1;
+
+pub const NFS4_FHSIZE: usize = 128;
+pub const NFS4_VERIFIER_SIZE: usize = 8;
+pub const NFS4_OTHER_SIZE: usize = 12;
+pub const NFS4_OPAQUE_LIMIT: usize = 1024;
+pub const NFS4_SESSIONID_SIZE: usize = 16;
+
+/// `Access4Args::access` and `Access4ResOk` bits
+pub const ACCESS4_READ: u32 = 0x0001;
+pub const ACCESS4_LOOKUP: u32 = 0x0002;
+pub const ACCESS4_MODIFY: u32 = 0x0004;
+pub const ACCESS4_EXTEND: u32 = 0x0008;
+pub const ACCESS4_DELETE: u32 = 0x0010;
+pub const ACCESS4_EXECUTE: u32 = 0x0020;
+
+/// `Open4Args::share_access` and `share_deny` values
+pub const OPEN4_SHARE_ACCESS_READ: u32 = 0x0001;
+pub const OPEN4_SHARE_ACCESS_WRITE: u32 = 0x0002;
+pub const OPEN4_SHARE_ACCESS_BOTH: u32 = 0x0003;
+pub const OPEN4_SHARE_DENY_NONE: u32 = 0x0000;
+pub const OPEN4_SHARE_DENY_READ: u32 = 0x0001;
+pub const OPEN4_SHARE_DENY_WRITE: u32 = 0x0002;
+pub const OPEN4_SHARE_DENY_BOTH: u32 = 0x0003;
+
+/// `Open4ResOk::rflags` bits
+pub const OPEN4_RESULT_CONFIRM: u32 = 0x0002;
+pub const OPEN4_RESULT_LOCKTYPE_POSIX: u32 = 0x0004;
+pub const OPEN4_RESULT_PRESERVE_UNLINKED: u32 = 0x0008;
+pub const OPEN4_RESULT_MAY_NOTIFY_LOCK: u32 = 0x0020;
+
+/// `ExchangeId4Args::eia_flags` and `ExchangeId4ResOk::eir_flags` bits
+pub const EXCHGID4_FLAG_SUPP_MOVED_REFER: u32 = 0x0000_0001;
+pub const EXCHGID4_FLAG_SUPP_MOVED_MIGR: u32 = 0x0000_0002;
+pub const EXCHGID4_FLAG_BIND_PRINC_STATEID: u32 = 0x0000_0100;
+pub const EXCHGID4_FLAG_USE_NON_PNFS: u32 = 0x0001_0000;
+pub const EXCHGID4_FLAG_USE_PNFS_MDS: u32 = 0x0002_0000;
+pub const EXCHGID4_FLAG_USE_PNFS_DS: u32 = 0x0004_0000;
+pub const EXCHGID4_FLAG_UPD_CONFIRMED_REC_A: u32 = 0x4000_0000;
+pub const EXCHGID4_FLAG_CONFIRMED_R: u32 = 0x8000_0000;
+
+/// `CreateSession4Args::csa_flags` bits
+pub const CREATE_SESSION4_FLAG_PERSIST: u32 = 0x0001;
+pub const CREATE_SESSION4_FLAG_CONN_BACK_CHAN: u32 = 0x0002;
+pub const CREATE_SESSION4_FLAG_CONN_RDMA: u32 = 0x0004;
+
+pub type Attrlist4 = VariableLengthBytes;
+pub type Changeid4 = u64;
+pub type Clientid4 = u64;
+pub type Count4 = u32;
+pub type Length4 = u64;
+pub type Mode4 = u32;
+pub type NfsCookie4 = u64;
+pub type NfsFh4 = VariableLengthBytes<NFS4_FHSIZE>;
+pub type Offset4 = u64;
+pub type Qop4 = u32;
+pub type Seqid4 = u32;
+pub type Sequenceid4 = u32;
+pub type Slotid4 = u32;
+pub type Sessionid4 = FixedLengthBytes<NFS4_SESSIONID_SIZE>;
+pub type Verifier4 = FixedLengthBytes<NFS4_VERIFIER_SIZE>;
+pub type SecOid4 = VariableLengthBytes;
+pub type Gsshandle4 = VariableLengthBytes;
+pub type Utf8strCis = String;
+pub type Utf8strCs = String;
+pub type Utf8strMixed = String;
+pub type Component4 = Utf8strCs;
+pub type Linktext4 = Utf8strCs;
+pub type Pathname4 = Vec<Component4>;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Nfsstat4 {
+    Nfs4Ok = 0,
+    Nfs4errPerm = 1,
+    Nfs4errNoent = 2,
+    Nfs4errIo = 5,
+    Nfs4errNxio = 6,
+    Nfs4errAccess = 13,
+    Nfs4errExist = 17,
+    Nfs4errXdev = 18,
+    Nfs4errNotdir = 20,
+    Nfs4errIsdir = 21,
+    Nfs4errInval = 22,
+    Nfs4errFbig = 27,
+    Nfs4errNospc = 28,
+    Nfs4errRofs = 30,
+    Nfs4errMlink = 31,
+    Nfs4errNametoolong = 63,
+    Nfs4errNotempty = 66,
+    Nfs4errDquot = 69,
+    Nfs4errStale = 70,
+    Nfs4errBadhandle = 10001,
+    Nfs4errBadCookie = 10003,
+    Nfs4errNotsupp = 10004,
+    Nfs4errToosmall = 10005,
+    Nfs4errServerfault = 10006,
+    Nfs4errBadtype = 10007,
+    Nfs4errDelay = 10008,
+    Nfs4errSame = 10009,
+    Nfs4errDenied = 10010,
+    Nfs4errExpired = 10011,
+    Nfs4errLocked = 10012,
+    Nfs4errGrace = 10013,
+    Nfs4errFhexpired = 10014,
+    Nfs4errShareDenied = 10015,
+    Nfs4errWrongsec = 10016,
+    Nfs4errClidInuse = 10017,
+    Nfs4errResource = 10018,
+    Nfs4errMoved = 10019,
+    Nfs4errNofilehandle = 10020,
+    Nfs4errMinorVersMismatch = 10021,
+    Nfs4errStaleClientid = 10022,
+    Nfs4errStaleStateid = 10023,
+    Nfs4errOldStateid = 10024,
+    Nfs4errBadStateid = 10025,
+    Nfs4errBadSeqid = 10026,
+    Nfs4errNotSame = 10027,
+    Nfs4errLockRange = 10028,
+    Nfs4errSymlink = 10029,
+    Nfs4errRestorefh = 10030,
+    Nfs4errLeaseMoved = 10031,
+    Nfs4errAttrnotsupp = 10032,
+    Nfs4errNoGrace = 10033,
+    Nfs4errReclaimBad = 10034,
+    Nfs4errReclaimConflict = 10035,
+    Nfs4errBadxdr = 10036,
+    Nfs4errLocksHeld = 10037,
+    Nfs4errOpenmode = 10038,
+    Nfs4errBadowner = 10039,
+    Nfs4errBadchar = 10040,
+    Nfs4errBadname = 10041,
+    Nfs4errBadRange = 10042,
+    Nfs4errLockNotsupp = 10043,
+    Nfs4errOpIllegal = 10044,
+    Nfs4errDeadlock = 10045,
+    Nfs4errFileOpen = 10046,
+    Nfs4errAdminRevoked = 10047,
+    Nfs4errCbPathDown = 10048,
+    Nfs4errBadiomode = 10049,
+    Nfs4errBadlayout = 10050,
+    Nfs4errBadSessionDigest = 10051,
+    Nfs4errBadsession = 10052,
+    Nfs4errBadslot = 10053,
+    Nfs4errCompleteAlready = 10054,
+    Nfs4errConnNotBoundToSession = 10055,
+    Nfs4errDelegAlreadyWanted = 10056,
+    Nfs4errBackChanBusy = 10057,
+    Nfs4errLayouttrylater = 10058,
+    Nfs4errLayoutunavailable = 10059,
+    Nfs4errNomatchingLayout = 10060,
+    Nfs4errRecallconflict = 10061,
+    Nfs4errUnknownLayouttype = 10062,
+    Nfs4errSeqMisordered = 10063,
+    Nfs4errSequencePos = 10064,
+    Nfs4errReqTooBig = 10065,
+    Nfs4errRepTooBig = 10066,
+    Nfs4errRepTooBigToCache = 10067,
+    Nfs4errRetryUncachedRep = 10068,
+    Nfs4errUnsafeCompound = 10069,
+    Nfs4errTooManyOps = 10070,
+    Nfs4errOpNotInSession = 10071,
+    Nfs4errHashAlgUnsupp = 10072,
+    Nfs4errClientidBusy = 10074,
+    Nfs4errPnfsIoHole = 10075,
+    Nfs4errSeqFalseRetry = 10076,
+    Nfs4errBadHighSlot = 10077,
+    Nfs4errDeadsession = 10078,
+    Nfs4errEncrAlgUnsupp = 10079,
+    Nfs4errPnfsNoLayout = 10080,
+    Nfs4errNotOnlyOp = 10081,
+    Nfs4errWrongCred = 10082,
+    Nfs4errWrongType = 10083,
+    Nfs4errDirdelegUnavail = 10084,
+    Nfs4errRejectDeleg = 10085,
+    Nfs4errReturnconflict = 10086,
+    Nfs4errDelegRevoked = 10087,
+    /// status not listed in RFC 8881, kept so replies can still be decoded
+    #[default_arm]
+    Unknown(u32),
+}
+
+/// Operation result union switched on `Nfsstat4`, `resok4` on `Nfs4Ok` and
+/// void otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nfs4Result<T> {
+    Ok(T),
+    /// failure with any status but `Nfs4Ok`
+    Fail(Nfsstat4),
+}
+
+impl<T> Nfs4Result<T> {
+    pub fn status(&self) -> Nfsstat4 {
+        match self {
+            Nfs4Result::Ok(_) => Nfsstat4::Nfs4Ok,
+            Nfs4Result::Fail(status) => *status,
+        }
+    }
+
+    pub fn into_result(self) -> std::result::Result<T, Nfsstat4> {
+        match self {
+            Nfs4Result::Ok(resok) => Ok(resok),
+            Nfs4Result::Fail(status) => Err(status),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Nfs4Result<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Nfs4Result::Ok(resok) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&Nfsstat4::Nfs4Ok)?;
+                tuple.serialize_element(resok)?;
+                tuple.end()
+            }
+            Nfs4Result::Fail(Nfsstat4::Nfs4Ok) => Err(serde::ser::Error::custom(
+                "failed result with status NFS4_OK",
+            )),
+            Nfs4Result::Fail(status) => status.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Nfs4Result<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for Visitor<T> {
+            type Value = Nfs4Result<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("nfsstat4 followed by resok4 on NFS4_OK")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let status: Nfsstat4 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                match status {
+                    Nfsstat4::Nfs4Ok => Ok(Nfs4Result::Ok(
+                        seq.next_element()?
+                            .ok_or_else(|| de::Error::invalid_length(1, &self))?,
+                    )),
+                    status => Ok(Nfs4Result::Fail(status)),
+                }
+            }
+        }
+
+        deserializer.deserialize_tuple(2, Visitor(PhantomData))
+    }
+}
+
+/// Set of attribute numbers, bit `n % 32` of word `n / 32` standing for attribute `n`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Bitmap4(pub Vec<u32>);
+
+impl Bitmap4 {
+    pub fn new() -> Self {
+        Bitmap4(Vec::new())
+    }
+
+    pub fn from_ids(ids: &[u32]) -> Self {
+        let mut bitmap = Bitmap4::new();
+        for &id in ids {
+            bitmap.insert(id);
+        }
+        bitmap
+    }
+
+    pub fn insert(&mut self, id: u32) {
+        let word = id as usize / 32;
+        if self.0.len() <= word {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (id % 32);
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.0
+            .get(id as usize / 32)
+            .is_some_and(|word| word & (1 << (id % 32)) != 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    /// attribute numbers in the set, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.0.iter().enumerate().flat_map(|(word, &bits)| {
+            (0..32)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| word as u32 * 32 + bit)
+        })
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum NfsFtype4 {
+    Nf4reg = 1,
+    Nf4dir = 2,
+    Nf4blk = 3,
+    Nf4chr = 4,
+    Nf4lnk = 5,
+    Nf4sock = 6,
+    Nf4fifo = 7,
+    Nf4attrdir = 8,
+    Nf4namedattr = 9,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nfstime4 {
+    pub seconds: i64,
+    pub nseconds: u32,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum TimeHow4 {
+    SetToServerTime4 = 0,
+    SetToClientTime4 = 1,
+}
+
+/// `settime4`, switched on `TimeHow4`
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Settime4 {
+    SetToServerTime4 = 0,
+    SetToClientTime4(Nfstime4) = 1,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fsid4 {
+    pub major: u64,
+    pub minor: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsLocation4 {
+    pub server: Vec<Utf8strCis>,
+    pub rootpath: Pathname4,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsLocations4 {
+    pub fs_root: Pathname4,
+    pub locations: Vec<FsLocation4>,
+}
+
+/// access control entry; `type`, `flag` and `access_mask` hold the `ACE4_*` values
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nfsace4 {
+    pub r#type: u32,
+    pub flag: u32,
+    pub access_mask: u32,
+    pub who: Utf8strMixed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Specdata4 {
+    pub specdata1: u32,
+    pub specdata2: u32,
+}
+
+pub const FATTR4_SUPPORTED_ATTRS: u32 = 0;
+pub const FATTR4_TYPE: u32 = 1;
+pub const FATTR4_FH_EXPIRE_TYPE: u32 = 2;
+pub const FATTR4_CHANGE: u32 = 3;
+pub const FATTR4_SIZE: u32 = 4;
+pub const FATTR4_LINK_SUPPORT: u32 = 5;
+pub const FATTR4_SYMLINK_SUPPORT: u32 = 6;
+pub const FATTR4_NAMED_ATTR: u32 = 7;
+pub const FATTR4_FSID: u32 = 8;
+pub const FATTR4_UNIQUE_HANDLES: u32 = 9;
+pub const FATTR4_LEASE_TIME: u32 = 10;
+pub const FATTR4_RDATTR_ERROR: u32 = 11;
+pub const FATTR4_ACL: u32 = 12;
+pub const FATTR4_ACLSUPPORT: u32 = 13;
+pub const FATTR4_ARCHIVE: u32 = 14;
+pub const FATTR4_CANSETTIME: u32 = 15;
+pub const FATTR4_CASE_INSENSITIVE: u32 = 16;
+pub const FATTR4_CASE_PRESERVING: u32 = 17;
+pub const FATTR4_CHOWN_RESTRICTED: u32 = 18;
+pub const FATTR4_FILEHANDLE: u32 = 19;
+pub const FATTR4_FILEID: u32 = 20;
+pub const FATTR4_FILES_AVAIL: u32 = 21;
+pub const FATTR4_FILES_FREE: u32 = 22;
+pub const FATTR4_FILES_TOTAL: u32 = 23;
+pub const FATTR4_FS_LOCATIONS: u32 = 24;
+pub const FATTR4_HIDDEN: u32 = 25;
+pub const FATTR4_HOMOGENEOUS: u32 = 26;
+pub const FATTR4_MAXFILESIZE: u32 = 27;
+pub const FATTR4_MAXLINK: u32 = 28;
+pub const FATTR4_MAXNAME: u32 = 29;
+pub const FATTR4_MAXREAD: u32 = 30;
+pub const FATTR4_MAXWRITE: u32 = 31;
+pub const FATTR4_MIMETYPE: u32 = 32;
+pub const FATTR4_MODE: u32 = 33;
+pub const FATTR4_NO_TRUNC: u32 = 34;
+pub const FATTR4_NUMLINKS: u32 = 35;
+pub const FATTR4_OWNER: u32 = 36;
+pub const FATTR4_OWNER_GROUP: u32 = 37;
+pub const FATTR4_QUOTA_AVAIL_HARD: u32 = 38;
+pub const FATTR4_QUOTA_AVAIL_SOFT: u32 = 39;
+pub const FATTR4_QUOTA_USED: u32 = 40;
+pub const FATTR4_RAWDEV: u32 = 41;
+pub const FATTR4_SPACE_AVAIL: u32 = 42;
+pub const FATTR4_SPACE_FREE: u32 = 43;
+pub const FATTR4_SPACE_TOTAL: u32 = 44;
+pub const FATTR4_SPACE_USED: u32 = 45;
+pub const FATTR4_SYSTEM: u32 = 46;
+pub const FATTR4_TIME_ACCESS: u32 = 47;
+pub const FATTR4_TIME_ACCESS_SET: u32 = 48;
+pub const FATTR4_TIME_BACKUP: u32 = 49;
+pub const FATTR4_TIME_CREATE: u32 = 50;
+pub const FATTR4_TIME_DELTA: u32 = 51;
+pub const FATTR4_TIME_METADATA: u32 = 52;
+pub const FATTR4_TIME_MODIFY: u32 = 53;
+pub const FATTR4_TIME_MODIFY_SET: u32 = 54;
+pub const FATTR4_MOUNTED_ON_FILEID: u32 = 55;
+pub const FATTR4_SUPPATTR_EXCLCREAT: u32 = 75;
+
+/// Value of one attribute, for the attributes of NFSv4.0 and `suppattr_exclcreat`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttrValue {
+    SupportedAttrs(Bitmap4),
+    Type(NfsFtype4),
+    FhExpireType(u32),
+    Change(Changeid4),
+    Size(u64),
+    LinkSupport(bool),
+    SymlinkSupport(bool),
+    NamedAttr(bool),
+    Fsid(Fsid4),
+    UniqueHandles(bool),
+    LeaseTime(u32),
+    RdattrError(Nfsstat4),
+    Acl(Vec<Nfsace4>),
+    Aclsupport(u32),
+    Archive(bool),
+    Cansettime(bool),
+    CaseInsensitive(bool),
+    CasePreserving(bool),
+    ChownRestricted(bool),
+    Filehandle(NfsFh4),
+    Fileid(u64),
+    FilesAvail(u64),
+    FilesFree(u64),
+    FilesTotal(u64),
+    FsLocations(FsLocations4),
+    Hidden(bool),
+    Homogeneous(bool),
+    Maxfilesize(u64),
+    Maxlink(u32),
+    Maxname(u32),
+    Maxread(u64),
+    Maxwrite(u64),
+    Mimetype(Utf8strCs),
+    Mode(Mode4),
+    NoTrunc(bool),
+    Numlinks(u32),
+    Owner(Utf8strMixed),
+    OwnerGroup(Utf8strMixed),
+    QuotaAvailHard(u64),
+    QuotaAvailSoft(u64),
+    QuotaUsed(u64),
+    Rawdev(Specdata4),
+    SpaceAvail(u64),
+    SpaceFree(u64),
+    SpaceTotal(u64),
+    SpaceUsed(u64),
+    System(bool),
+    TimeAccess(Nfstime4),
+    TimeAccessSet(Settime4),
+    TimeBackup(Nfstime4),
+    TimeCreate(Nfstime4),
+    TimeDelta(Nfstime4),
+    TimeMetadata(Nfstime4),
+    TimeModify(Nfstime4),
+    TimeModifySet(Settime4),
+    MountedOnFileid(u64),
+    SuppattrExclcreat(Bitmap4),
+}
+
+impl AttrValue {
+    /// attribute number, one of the `FATTR4_*` constants
+    pub fn id(&self) -> u32 {
+        match self {
+            AttrValue::SupportedAttrs(_) => FATTR4_SUPPORTED_ATTRS,
+            AttrValue::Type(_) => FATTR4_TYPE,
+            AttrValue::FhExpireType(_) => FATTR4_FH_EXPIRE_TYPE,
+            AttrValue::Change(_) => FATTR4_CHANGE,
+            AttrValue::Size(_) => FATTR4_SIZE,
+            AttrValue::LinkSupport(_) => FATTR4_LINK_SUPPORT,
+            AttrValue::SymlinkSupport(_) => FATTR4_SYMLINK_SUPPORT,
+            AttrValue::NamedAttr(_) => FATTR4_NAMED_ATTR,
+            AttrValue::Fsid(_) => FATTR4_FSID,
+            AttrValue::UniqueHandles(_) => FATTR4_UNIQUE_HANDLES,
+            AttrValue::LeaseTime(_) => FATTR4_LEASE_TIME,
+            AttrValue::RdattrError(_) => FATTR4_RDATTR_ERROR,
+            AttrValue::Acl(_) => FATTR4_ACL,
+            AttrValue::Aclsupport(_) => FATTR4_ACLSUPPORT,
+            AttrValue::Archive(_) => FATTR4_ARCHIVE,
+            AttrValue::Cansettime(_) => FATTR4_CANSETTIME,
+            AttrValue::CaseInsensitive(_) => FATTR4_CASE_INSENSITIVE,
+            AttrValue::CasePreserving(_) => FATTR4_CASE_PRESERVING,
+            AttrValue::ChownRestricted(_) => FATTR4_CHOWN_RESTRICTED,
+            AttrValue::Filehandle(_) => FATTR4_FILEHANDLE,
+            AttrValue::Fileid(_) => FATTR4_FILEID,
+            AttrValue::FilesAvail(_) => FATTR4_FILES_AVAIL,
+            AttrValue::FilesFree(_) => FATTR4_FILES_FREE,
+            AttrValue::FilesTotal(_) => FATTR4_FILES_TOTAL,
+            AttrValue::FsLocations(_) => FATTR4_FS_LOCATIONS,
+            AttrValue::Hidden(_) => FATTR4_HIDDEN,
+            AttrValue::Homogeneous(_) => FATTR4_HOMOGENEOUS,
+            AttrValue::Maxfilesize(_) => FATTR4_MAXFILESIZE,
+            AttrValue::Maxlink(_) => FATTR4_MAXLINK,
+            AttrValue::Maxname(_) => FATTR4_MAXNAME,
+            AttrValue::Maxread(_) => FATTR4_MAXREAD,
+            AttrValue::Maxwrite(_) => FATTR4_MAXWRITE,
+            AttrValue::Mimetype(_) => FATTR4_MIMETYPE,
+            AttrValue::Mode(_) => FATTR4_MODE,
+            AttrValue::NoTrunc(_) => FATTR4_NO_TRUNC,
+            AttrValue::Numlinks(_) => FATTR4_NUMLINKS,
+            AttrValue::Owner(_) => FATTR4_OWNER,
+            AttrValue::OwnerGroup(_) => FATTR4_OWNER_GROUP,
+            AttrValue::QuotaAvailHard(_) => FATTR4_QUOTA_AVAIL_HARD,
+            AttrValue::QuotaAvailSoft(_) => FATTR4_QUOTA_AVAIL_SOFT,
+            AttrValue::QuotaUsed(_) => FATTR4_QUOTA_USED,
+            AttrValue::Rawdev(_) => FATTR4_RAWDEV,
+            AttrValue::SpaceAvail(_) => FATTR4_SPACE_AVAIL,
+            AttrValue::SpaceFree(_) => FATTR4_SPACE_FREE,
+            AttrValue::SpaceTotal(_) => FATTR4_SPACE_TOTAL,
+            AttrValue::SpaceUsed(_) => FATTR4_SPACE_USED,
+            AttrValue::System(_) => FATTR4_SYSTEM,
+            AttrValue::TimeAccess(_) => FATTR4_TIME_ACCESS,
+            AttrValue::TimeAccessSet(_) => FATTR4_TIME_ACCESS_SET,
+            AttrValue::TimeBackup(_) => FATTR4_TIME_BACKUP,
+            AttrValue::TimeCreate(_) => FATTR4_TIME_CREATE,
+            AttrValue::TimeDelta(_) => FATTR4_TIME_DELTA,
+            AttrValue::TimeMetadata(_) => FATTR4_TIME_METADATA,
+            AttrValue::TimeModify(_) => FATTR4_TIME_MODIFY,
+            AttrValue::TimeModifySet(_) => FATTR4_TIME_MODIFY_SET,
+            AttrValue::MountedOnFileid(_) => FATTR4_MOUNTED_ON_FILEID,
+            AttrValue::SuppattrExclcreat(_) => FATTR4_SUPPATTR_EXCLCREAT,
+        }
+    }
+
+    /// append the value alone, as it appears in `attr_vals`
+    fn encode_into(&self, buf: &mut Vec<u8>) -> Result<()> {
+        match self {
+            AttrValue::SupportedAttrs(v) | AttrValue::SuppattrExclcreat(v) => to_bytes_into(v, buf),
+            AttrValue::Type(v) => to_bytes_into(v, buf),
+            AttrValue::RdattrError(v) => to_bytes_into(v, buf),
+            AttrValue::Fsid(v) => to_bytes_into(v, buf),
+            AttrValue::Acl(v) => to_bytes_into(v, buf),
+            AttrValue::Filehandle(v) => to_bytes_into(v, buf),
+            AttrValue::FsLocations(v) => to_bytes_into(v, buf),
+            AttrValue::Rawdev(v) => to_bytes_into(v, buf),
+            AttrValue::Mimetype(v) | AttrValue::Owner(v) | AttrValue::OwnerGroup(v) => {
+                to_bytes_into(v, buf)
+            }
+            AttrValue::TimeAccessSet(v) | AttrValue::TimeModifySet(v) => to_bytes_into(v, buf),
+            AttrValue::LinkSupport(v)
+            | AttrValue::SymlinkSupport(v)
+            | AttrValue::NamedAttr(v)
+            | AttrValue::UniqueHandles(v)
+            | AttrValue::Archive(v)
+            | AttrValue::Cansettime(v)
+            | AttrValue::CaseInsensitive(v)
+            | AttrValue::CasePreserving(v)
+            | AttrValue::ChownRestricted(v)
+            | AttrValue::Hidden(v)
+            | AttrValue::Homogeneous(v)
+            | AttrValue::NoTrunc(v)
+            | AttrValue::System(v) => to_bytes_into(v, buf),
+            AttrValue::FhExpireType(v)
+            | AttrValue::LeaseTime(v)
+            | AttrValue::Aclsupport(v)
+            | AttrValue::Maxlink(v)
+            | AttrValue::Maxname(v)
+            | AttrValue::Mode(v)
+            | AttrValue::Numlinks(v) => to_bytes_into(v, buf),
+            AttrValue::Change(v)
+            | AttrValue::Size(v)
+            | AttrValue::Fileid(v)
+            | AttrValue::FilesAvail(v)
+            | AttrValue::FilesFree(v)
+            | AttrValue::FilesTotal(v)
+            | AttrValue::Maxfilesize(v)
+            | AttrValue::Maxread(v)
+            | AttrValue::Maxwrite(v)
+            | AttrValue::QuotaAvailHard(v)
+            | AttrValue::QuotaAvailSoft(v)
+            | AttrValue::QuotaUsed(v)
+            | AttrValue::SpaceAvail(v)
+            | AttrValue::SpaceFree(v)
+            | AttrValue::SpaceTotal(v)
+            | AttrValue::SpaceUsed(v)
+            | AttrValue::MountedOnFileid(v) => to_bytes_into(v, buf),
+            AttrValue::TimeAccess(v)
+            | AttrValue::TimeBackup(v)
+            | AttrValue::TimeCreate(v)
+            | AttrValue::TimeDelta(v)
+            | AttrValue::TimeMetadata(v)
+            | AttrValue::TimeModify(v) => to_bytes_into(v, buf),
+        }
+    }
+
+    /// decode the value of attribute `id` at the start of `input`, returning
+    /// it with the input following it
+    fn decode(id: u32, input: &[u8]) -> Result<(AttrValue, &[u8])> {
+        fn value<'a, T: Deserialize<'a>>(
+            input: &'a [u8],
+            variant: fn(T) -> AttrValue,
+        ) -> Result<(AttrValue, &'a [u8])> {
+            let (value, rest) = from_bytes_prefix(input)?;
+            Ok((variant(value), rest))
+        }
+
+        match id {
+            FATTR4_SUPPORTED_ATTRS => value(input, AttrValue::SupportedAttrs),
+            FATTR4_TYPE => value(input, AttrValue::Type),
+            FATTR4_FH_EXPIRE_TYPE => value(input, AttrValue::FhExpireType),
+            FATTR4_CHANGE => value(input, AttrValue::Change),
+            FATTR4_SIZE => value(input, AttrValue::Size),
+            FATTR4_LINK_SUPPORT => value(input, AttrValue::LinkSupport),
+            FATTR4_SYMLINK_SUPPORT => value(input, AttrValue::SymlinkSupport),
+            FATTR4_NAMED_ATTR => value(input, AttrValue::NamedAttr),
+            FATTR4_FSID => value(input, AttrValue::Fsid),
+            FATTR4_UNIQUE_HANDLES => value(input, AttrValue::UniqueHandles),
+            FATTR4_LEASE_TIME => value(input, AttrValue::LeaseTime),
+            FATTR4_RDATTR_ERROR => value(input, AttrValue::RdattrError),
+            FATTR4_ACL => value(input, AttrValue::Acl),
+            FATTR4_ACLSUPPORT => value(input, AttrValue::Aclsupport),
+            FATTR4_ARCHIVE => value(input, AttrValue::Archive),
+            FATTR4_CANSETTIME => value(input, AttrValue::Cansettime),
+            FATTR4_CASE_INSENSITIVE => value(input, AttrValue::CaseInsensitive),
+            FATTR4_CASE_PRESERVING => value(input, AttrValue::CasePreserving),
+            FATTR4_CHOWN_RESTRICTED => value(input, AttrValue::ChownRestricted),
+            FATTR4_FILEHANDLE => value(input, AttrValue::Filehandle),
+            FATTR4_FILEID => value(input, AttrValue::Fileid),
+            FATTR4_FILES_AVAIL => value(input, AttrValue::FilesAvail),
+            FATTR4_FILES_FREE => value(input, AttrValue::FilesFree),
+            FATTR4_FILES_TOTAL => value(input, AttrValue::FilesTotal),
+            FATTR4_FS_LOCATIONS => value(input, AttrValue::FsLocations),
+            FATTR4_HIDDEN => value(input, AttrValue::Hidden),
+            FATTR4_HOMOGENEOUS => value(input, AttrValue::Homogeneous),
+            FATTR4_MAXFILESIZE => value(input, AttrValue::Maxfilesize),
+            FATTR4_MAXLINK => value(input, AttrValue::Maxlink),
+            FATTR4_MAXNAME => value(input, AttrValue::Maxname),
+            FATTR4_MAXREAD => value(input, AttrValue::Maxread),
+            FATTR4_MAXWRITE => value(input, AttrValue::Maxwrite),
+            FATTR4_MIMETYPE => value(input, AttrValue::Mimetype),
+            FATTR4_MODE => value(input, AttrValue::Mode),
+            FATTR4_NO_TRUNC => value(input, AttrValue::NoTrunc),
+            FATTR4_NUMLINKS => value(input, AttrValue::Numlinks),
+            FATTR4_OWNER => value(input, AttrValue::Owner),
+            FATTR4_OWNER_GROUP => value(input, AttrValue::OwnerGroup),
+            FATTR4_QUOTA_AVAIL_HARD => value(input, AttrValue::QuotaAvailHard),
+            FATTR4_QUOTA_AVAIL_SOFT => value(input, AttrValue::QuotaAvailSoft),
+            FATTR4_QUOTA_USED => value(input, AttrValue::QuotaUsed),
+            FATTR4_RAWDEV => value(input, AttrValue::Rawdev),
+            FATTR4_SPACE_AVAIL => value(input, AttrValue::SpaceAvail),
+            FATTR4_SPACE_FREE => value(input, AttrValue::SpaceFree),
+            FATTR4_SPACE_TOTAL => value(input, AttrValue::SpaceTotal),
+            FATTR4_SPACE_USED => value(input, AttrValue::SpaceUsed),
+            FATTR4_SYSTEM => value(input, AttrValue::System),
+            FATTR4_TIME_ACCESS => value(input, AttrValue::TimeAccess),
+            FATTR4_TIME_ACCESS_SET => value(input, AttrValue::TimeAccessSet),
+            FATTR4_TIME_BACKUP => value(input, AttrValue::TimeBackup),
+            FATTR4_TIME_CREATE => value(input, AttrValue::TimeCreate),
+            FATTR4_TIME_DELTA => value(input, AttrValue::TimeDelta),
+            FATTR4_TIME_METADATA => value(input, AttrValue::TimeMetadata),
+            FATTR4_TIME_MODIFY => value(input, AttrValue::TimeModify),
+            FATTR4_TIME_MODIFY_SET => value(input, AttrValue::TimeModifySet),
+            FATTR4_MOUNTED_ON_FILEID => value(input, AttrValue::MountedOnFileid),
+            FATTR4_SUPPATTR_EXCLCREAT => value(input, AttrValue::SuppattrExclcreat),
+            id => Err(Error::UnknownAttr(id)),
+        }
+    }
+}
+
+/// Attributes as on the wire: the set of attributes present, and their
+/// values concatenated in ascending attribute number.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fattr4 {
+    pub attrmask: Bitmap4,
+    pub attr_vals: Attrlist4,
+}
+
+impl Fattr4 {
+    /// encode `values` in bitmap order; a later value of an attribute
+    /// replaces an earlier one
+    pub fn new(mut values: Vec<AttrValue>) -> Result<Self> {
+        // stable, so the last value of an attribute is the last of its run
+        values.sort_by_key(AttrValue::id);
+        let mut attrmask = Bitmap4::new();
+        let mut attr_vals = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            if values
+                .get(i + 1)
+                .is_some_and(|next| next.id() == value.id())
+            {
+                continue;
+            }
+            attrmask.insert(value.id());
+            value.encode_into(&mut attr_vals)?;
+        }
+        Ok(Fattr4 {
+            attrmask,
+            attr_vals: attr_vals.try_into()?,
+        })
+    }
+
+    /// decode the values of `attr_vals` in bitmap order, failing with
+    /// `Error::UnknownAttr` on an attribute `AttrValue` has no variant for,
+    /// since the values following it cannot be found
+    pub fn values(&self) -> Result<Vec<AttrValue>> {
+        let mut input: &[u8] = &self.attr_vals;
+        let mut values = Vec::new();
+        for id in self.attrmask.iter() {
+            let (value, rest) = AttrValue::decode(id, input)?;
+            values.push(value);
+            input = rest;
+        }
+        if !input.is_empty() {
+            return Err(Error::TrailingBytes);
+        }
+        Ok(values)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeInfo4 {
+    pub atomic: bool,
+    pub before: Changeid4,
+    pub after: Changeid4,
+}
+
+/// `netaddr4` and `clientaddr4`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Netaddr4 {
+    pub na_r_netid: String,
+    /// universal address whose format depends on `na_r_netid`, e.g.
+    /// `192.0.2.1.8.1` for `tcp`; see `UniversalAddr`
+    pub na_r_addr: String,
+}
+
+impl Netaddr4 {
+    /// parses `na_r_addr` as an IP universal address, failing with
+    /// `Error::InvalidUniversalAddr` for other formats
+    pub fn to_socket_addr(&self) -> Result<SocketAddr> {
+        Ok(self.na_r_addr.parse::<UniversalAddr>()?.into())
+    }
+}
+
+pub type Clientaddr4 = Netaddr4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CbClient4 {
+    pub cb_program: u32,
+    pub cb_location: Netaddr4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stateid4 {
+    pub seqid: u32,
+    pub other: FixedLengthBytes<NFS4_OTHER_SIZE>,
+}
+
+impl Stateid4 {
+    /// the special stateid of all zeros, for I/O without open or lock state
+    pub fn anonymous() -> Self {
+        Stateid4 {
+            seqid: 0,
+            other: FixedLengthBytes::new([0; NFS4_OTHER_SIZE]),
+        }
+    }
+
+    /// the special stateid of all ones, for reads bypassing locks
+    pub fn read_bypass() -> Self {
+        Stateid4 {
+            seqid: u32::MAX,
+            other: FixedLengthBytes::new([0xff; NFS4_OTHER_SIZE]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsClientId4 {
+    pub verifier: Verifier4,
+    pub id: VariableLengthBytes<NFS4_OPAQUE_LIMIT>,
+}
+
+/// `state_owner4`, `open_owner4` and `lock_owner4`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateOwner4 {
+    pub clientid: Clientid4,
+    pub owner: VariableLengthBytes<NFS4_OPAQUE_LIMIT>,
+}
+
+pub type OpenOwner4 = StateOwner4;
+pub type LockOwner4 = StateOwner4;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum NfsLockType4 {
+    ReadLt = 1,
+    WriteLt = 2,
+    ReadwLt = 3,
+    WritewLt = 4,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum StableHow4 {
+    Unstable4 = 0,
+    DataSync4 = 1,
+    FileSync4 = 2,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum NfsOpnum4 {
+    OpAccess = 3,
+    OpClose = 4,
+    OpCommit = 5,
+    OpCreate = 6,
+    OpDelegpurge = 7,
+    OpDelegreturn = 8,
+    OpGetattr = 9,
+    OpGetfh = 10,
+    OpLink = 11,
+    OpLock = 12,
+    OpLockt = 13,
+    OpLocku = 14,
+    OpLookup = 15,
+    OpLookupp = 16,
+    OpNverify = 17,
+    OpOpen = 18,
+    OpOpenattr = 19,
+    OpOpenConfirm = 20,
+    OpOpenDowngrade = 21,
+    OpPutfh = 22,
+    OpPutpubfh = 23,
+    OpPutrootfh = 24,
+    OpRead = 25,
+    OpReaddir = 26,
+    OpReadlink = 27,
+    OpRemove = 28,
+    OpRename = 29,
+    OpRenew = 30,
+    OpRestorefh = 31,
+    OpSavefh = 32,
+    OpSecinfo = 33,
+    OpSetattr = 34,
+    OpSetclientid = 35,
+    OpSetclientidConfirm = 36,
+    OpVerify = 37,
+    OpWrite = 38,
+    OpReleaseLockowner = 39,
+    OpBackchannelCtl = 40,
+    OpBindConnToSession = 41,
+    OpExchangeId = 42,
+    OpCreateSession = 43,
+    OpDestroySession = 44,
+    OpFreeStateid = 45,
+    OpGetDirDelegation = 46,
+    OpGetdeviceinfo = 47,
+    OpGetdevicelist = 48,
+    OpLayoutcommit = 49,
+    OpLayoutget = 50,
+    OpLayoutreturn = 51,
+    OpSecinfoNoName = 52,
+    OpSequence = 53,
+    OpSetSsv = 54,
+    OpTestStateid = 55,
+    OpWantDelegation = 56,
+    OpDestroyClientid = 57,
+    OpReclaimComplete = 58,
+    OpIllegal = 10044,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access4Args {
+    pub access: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access4ResOk {
+    pub supported: u32,
+    pub access: u32,
+}
+
+pub type Access4Res = Nfs4Result<Access4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Close4Args {
+    pub seqid: Seqid4,
+    pub open_stateid: Stateid4,
+}
+
+/// the `open_stateid` on success
+pub type Close4Res = Nfs4Result<Stateid4>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit4Args {
+    pub offset: Offset4,
+    pub count: Count4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit4ResOk {
+    pub writeverf: Verifier4,
+}
+
+pub type Commit4Res = Nfs4Result<Commit4ResOk>;
+
+/// `createtype4`, switched on `NfsFtype4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Createtype4 {
+    Nf4dir = 2,
+    Nf4blk(Specdata4) = 3,
+    Nf4chr(Specdata4) = 4,
+    Nf4lnk(Linktext4) = 5,
+    Nf4sock = 6,
+    Nf4fifo = 7,
+    /// a type `CREATE` does not create
+    #[default_arm]
+    Other(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Create4Args {
+    pub objtype: Createtype4,
+    pub objname: Component4,
+    pub createattrs: Fattr4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Create4ResOk {
+    pub cinfo: ChangeInfo4,
+    pub attrset: Bitmap4,
+}
+
+pub type Create4Res = Nfs4Result<Create4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegpurge4Args {
+    pub clientid: Clientid4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegreturn4Args {
+    pub deleg_stateid: Stateid4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Getattr4Args {
+    pub attr_request: Bitmap4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Getattr4ResOk {
+    pub obj_attributes: Fattr4,
+}
+
+pub type Getattr4Res = Nfs4Result<Getattr4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Getfh4ResOk {
+    pub object: NfsFh4,
+}
+
+pub type Getfh4Res = Nfs4Result<Getfh4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link4Args {
+    pub newname: Component4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link4ResOk {
+    pub cinfo: ChangeInfo4,
+}
+
+pub type Link4Res = Nfs4Result<Link4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenToLockOwner4 {
+    pub open_seqid: Seqid4,
+    pub open_stateid: Stateid4,
+    pub lock_seqid: Seqid4,
+    pub lock_owner: LockOwner4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExistLockOwner4 {
+    pub lock_stateid: Stateid4,
+    pub lock_seqid: Seqid4,
+}
+
+/// `locker4`, switched on the bool `new_lock_owner`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Locker4 {
+    LockOwner(ExistLockOwner4) = 0,
+    OpenOwner(OpenToLockOwner4) = 1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lock4Args {
+    pub locktype: NfsLockType4,
+    pub reclaim: bool,
+    pub offset: Offset4,
+    pub length: Length4,
+    pub locker: Locker4,
+}
+
+/// conflicting lock, returned with `NFS4ERR_DENIED`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lock4Denied {
+    pub offset: Offset4,
+    pub length: Length4,
+    pub locktype: NfsLockType4,
+    pub owner: LockOwner4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lock4ResOk {
+    pub lock_stateid: Stateid4,
+}
+
+/// `LOCK4res`, switched on `Nfsstat4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Lock4Res {
+    Ok(Lock4ResOk) = 0,
+    Denied(Lock4Denied) = 10010,
+    /// any other status
+    #[default_arm]
+    Fail(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockt4Args {
+    pub locktype: NfsLockType4,
+    pub offset: Offset4,
+    pub length: Length4,
+    pub owner: LockOwner4,
+}
+
+/// `LOCKT4res`, switched on `Nfsstat4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Lockt4Res {
+    Ok = 0,
+    Denied(Lock4Denied) = 10010,
+    /// any other status
+    #[default_arm]
+    Fail(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Locku4Args {
+    pub locktype: NfsLockType4,
+    pub seqid: Seqid4,
+    pub lock_stateid: Stateid4,
+    pub offset: Offset4,
+    pub length: Length4,
+}
+
+/// the `lock_stateid` on success
+pub type Locku4Res = Nfs4Result<Stateid4>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lookup4Args {
+    pub objname: Component4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nverify4Args {
+    pub obj_attributes: Fattr4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatVerfAttr {
+    pub cva_verf: Verifier4,
+    pub cva_attrs: Fattr4,
+}
+
+/// `createhow4`, switched on `createmode4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Createhow4 {
+    Unchecked4(Fattr4) = 0,
+    Guarded4(Fattr4) = 1,
+    Exclusive4(Verifier4) = 2,
+    /// NFSv4.1 only
+    Exclusive4_1(CreatVerfAttr) = 3,
+}
+
+/// `openflag4`, switched on `opentype4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Openflag4 {
+    Open4Nocreate = 0,
+    Open4Create(Createhow4) = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsModifiedLimit4 {
+    pub num_blocks: u32,
+    pub bytes_per_block: u32,
+}
+
+/// `nfs_space_limit4`, switched on `limit_by4`
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum NfsSpaceLimit4 {
+    NfsLimitSize(u64) = 1,
+    NfsLimitBlocks(NfsModifiedLimit4) = 2,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum OpenDelegationType4 {
+    OpenDelegateNone = 0,
+    OpenDelegateRead = 1,
+    OpenDelegateWrite = 2,
+    /// NFSv4.1 only
+    OpenDelegateNoneExt = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenClaimDelegateCur4 {
+    pub delegate_stateid: Stateid4,
+    pub file: Component4,
+}
+
+/// `open_claim4`, switched on `open_claim_type4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum OpenClaim4 {
+    ClaimNull(Component4) = 0,
+    ClaimPrevious(OpenDelegationType4) = 1,
+    ClaimDelegateCur(OpenClaimDelegateCur4) = 2,
+    ClaimDelegatePrev(Component4) = 3,
+    /// NFSv4.1 only
+    ClaimFh = 4,
+    /// NFSv4.1 only
+    ClaimDelegCurFh(Stateid4) = 5,
+    /// NFSv4.1 only
+    ClaimDelegPrevFh = 6,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Open4Args {
+    pub seqid: Seqid4,
+    pub share_access: u32,
+    pub share_deny: u32,
+    pub owner: OpenOwner4,
+    pub openhow: Openflag4,
+    pub claim: OpenClaim4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenReadDelegation4 {
+    pub stateid: Stateid4,
+    pub recall: bool,
+    pub permissions: Nfsace4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenWriteDelegation4 {
+    pub stateid: Stateid4,
+    pub recall: bool,
+    pub space_limit: NfsSpaceLimit4,
+    pub permissions: Nfsace4,
+}
+
+/// `open_none_delegation4`, switched on `why_no_delegation4`
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum OpenNoneDelegation4 {
+    /// `ond_server_will_push_deleg`
+    Wnd4Contention(bool) = 1,
+    /// `ond_server_will_signal_avail`
+    Wnd4Resource(bool) = 2,
+    /// any other `WND4_*` reason
+    #[default_arm]
+    Other(u32),
+}
+
+/// `open_delegation4`, switched on `OpenDelegationType4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum OpenDelegation4 {
+    OpenDelegateNone = 0,
+    OpenDelegateRead(OpenReadDelegation4) = 1,
+    OpenDelegateWrite(OpenWriteDelegation4) = 2,
+    /// NFSv4.1 only
+    OpenDelegateNoneExt(OpenNoneDelegation4) = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Open4ResOk {
+    pub stateid: Stateid4,
+    pub cinfo: ChangeInfo4,
+    /// `OPEN4_RESULT_*` bits
+    pub rflags: u32,
+    pub attrset: Bitmap4,
+    pub delegation: OpenDelegation4,
+}
+
+pub type Open4Res = Nfs4Result<Open4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Openattr4Args {
+    pub createdir: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenConfirm4Args {
+    pub open_stateid: Stateid4,
+    pub seqid: Seqid4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenConfirm4ResOk {
+    pub open_stateid: Stateid4,
+}
+
+pub type OpenConfirm4Res = Nfs4Result<OpenConfirm4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenDowngrade4Args {
+    pub open_stateid: Stateid4,
+    pub seqid: Seqid4,
+    pub share_access: u32,
+    pub share_deny: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenDowngrade4ResOk {
+    pub open_stateid: Stateid4,
+}
+
+pub type OpenDowngrade4Res = Nfs4Result<OpenDowngrade4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Putfh4Args {
+    pub object: NfsFh4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Read4Args {
+    pub stateid: Stateid4,
+    pub offset: Offset4,
+    pub count: Count4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Read4ResOk {
+    pub eof: bool,
+    pub data: VariableLengthBytes,
+}
+
+pub type Read4Res = Nfs4Result<Read4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdir4Args {
+    pub cookie: NfsCookie4,
+    pub cookieverf: Verifier4,
+    pub dircount: Count4,
+    pub maxcount: Count4,
+    pub attr_request: Bitmap4,
+}
+
+/// `entry4` without its `nextentry` link, see `Dirlist4`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry4 {
+    pub cookie: NfsCookie4,
+    pub name: Component4,
+    pub attrs: Fattr4,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dirlist4 {
+    pub entries: OptionalList<Entry4>,
+    pub eof: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readdir4ResOk {
+    pub cookieverf: Verifier4,
+    pub reply: Dirlist4,
+}
+
+pub type Readdir4Res = Nfs4Result<Readdir4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readlink4ResOk {
+    pub link: Linktext4,
+}
+
+pub type Readlink4Res = Nfs4Result<Readlink4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remove4Args {
+    pub target: Component4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remove4ResOk {
+    pub cinfo: ChangeInfo4,
+}
+
+pub type Remove4Res = Nfs4Result<Remove4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename4Args {
+    pub oldname: Component4,
+    pub newname: Component4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename4ResOk {
+    pub source_cinfo: ChangeInfo4,
+    pub target_cinfo: ChangeInfo4,
+}
+
+pub type Rename4Res = Nfs4Result<Rename4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Renew4Args {
+    pub clientid: Clientid4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Secinfo4Args {
+    pub name: Component4,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum RpcGssSvc {
+    RpcGssSvcNone = 1,
+    RpcGssSvcIntegrity = 2,
+    RpcGssSvcPrivacy = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcsecGssInfo {
+    pub oid: SecOid4,
+    pub qop: Qop4,
+    pub service: RpcGssSvc,
+}
+
+/// `secinfo4`, switched on the RPC auth flavor
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Secinfo4 {
+    RpcsecGss(RpcsecGssInfo) = 6,
+    /// any flavor but `RPCSEC_GSS`, such as 0 for `AUTH_NONE` or 1 for `AUTH_SYS`
+    #[default_arm]
+    Flavor(u32),
+}
+
+/// result of `SECINFO` and `SECINFO_NO_NAME`
+pub type Secinfo4Res = Nfs4Result<Vec<Secinfo4>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setattr4Args {
+    pub stateid: Stateid4,
+    pub obj_attributes: Fattr4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setattr4Res {
+    pub status: Nfsstat4,
+    pub attrsset: Bitmap4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setclientid4Args {
+    pub client: NfsClientId4,
+    pub callback: CbClient4,
+    pub callback_ident: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Setclientid4ResOk {
+    pub clientid: Clientid4,
+    pub setclientid_confirm: Verifier4,
+}
+
+/// `SETCLIENTID4res`, switched on `Nfsstat4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum Setclientid4Res {
+    Ok(Setclientid4ResOk) = 0,
+    /// address of the client already using the id
+    ClidInuse(Clientaddr4) = 10017,
+    /// any other status
+    #[default_arm]
+    Fail(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetclientidConfirm4Args {
+    pub clientid: Clientid4,
+    pub setclientid_confirm: Verifier4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verify4Args {
+    pub obj_attributes: Fattr4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Write4Args {
+    pub stateid: Stateid4,
+    pub offset: Offset4,
+    pub stable: StableHow4,
+    pub data: VariableLengthBytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Write4ResOk {
+    pub count: Count4,
+    pub committed: StableHow4,
+    pub writeverf: Verifier4,
+}
+
+pub type Write4Res = Nfs4Result<Write4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseLockowner4Args {
+    pub lock_owner: LockOwner4,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum ChannelDirFromClient4 {
+    Cdfc4Fore = 0x1,
+    Cdfc4Back = 0x2,
+    Cdfc4ForeOrBoth = 0x3,
+    Cdfc4BackOrBoth = 0x7,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum ChannelDirFromServer4 {
+    Cdfs4Fore = 0x1,
+    Cdfs4Back = 0x2,
+    Cdfs4Both = 0x3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BindConnToSession4Args {
+    pub bctsa_sessid: Sessionid4,
+    pub bctsa_dir: ChannelDirFromClient4,
+    pub bctsa_use_conn_in_rdma_mode: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BindConnToSession4ResOk {
+    pub bctsr_sessid: Sessionid4,
+    pub bctsr_dir: ChannelDirFromServer4,
+    pub bctsr_use_conn_in_rdma_mode: bool,
+}
+
+pub type BindConnToSession4Res = Nfs4Result<BindConnToSession4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientOwner4 {
+    pub co_verifier: Verifier4,
+    pub co_ownerid: VariableLengthBytes<NFS4_OPAQUE_LIMIT>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerOwner4 {
+    pub so_minor_id: u64,
+    pub so_major_id: VariableLengthBytes<NFS4_OPAQUE_LIMIT>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProtectOps4 {
+    pub spo_must_enforce: Bitmap4,
+    pub spo_must_allow: Bitmap4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SsvSpParms4 {
+    pub ssp_ops: StateProtectOps4,
+    pub ssp_hash_algs: Vec<SecOid4>,
+    pub ssp_encr_algs: Vec<SecOid4>,
+    pub ssp_window: u32,
+    pub ssp_num_gss_handles: u32,
+}
+
+/// `state_protect4_a`, switched on `state_protect_how4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum StateProtect4A {
+    Sp4None = 0,
+    Sp4MachCred(StateProtectOps4) = 1,
+    Sp4Ssv(SsvSpParms4) = 2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SsvProtInfo4 {
+    pub spi_ops: StateProtectOps4,
+    pub spi_hash_alg: u32,
+    pub spi_encr_alg: u32,
+    pub spi_ssv_len: u32,
+    pub spi_window: u32,
+    pub spi_handles: Vec<Gsshandle4>,
+}
+
+/// `state_protect4_r`, switched on `state_protect_how4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum StateProtect4R {
+    Sp4None = 0,
+    Sp4MachCred(StateProtectOps4) = 1,
+    Sp4Ssv(SsvProtInfo4) = 2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsImplId4 {
+    pub nii_domain: Utf8strCis,
+    pub nii_name: Utf8strCs,
+    pub nii_date: Nfstime4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeId4Args {
+    pub eia_clientowner: ClientOwner4,
+    /// `EXCHGID4_FLAG_*` bits
+    pub eia_flags: u32,
+    pub eia_state_protect: StateProtect4A,
+    /// `nfs_impl_id4<1>`, which has the wire form of an optional
+    pub eia_client_impl_id: Option<NfsImplId4>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeId4ResOk {
+    pub eir_clientid: Clientid4,
+    pub eir_sequenceid: Sequenceid4,
+    pub eir_flags: u32,
+    pub eir_state_protect: StateProtect4R,
+    pub eir_server_owner: ServerOwner4,
+    pub eir_server_scope: VariableLengthBytes<NFS4_OPAQUE_LIMIT>,
+    /// `nfs_impl_id4<1>`, which has the wire form of an optional
+    pub eir_server_impl_id: Option<NfsImplId4>,
+}
+
+pub type ExchangeId4Res = Nfs4Result<ExchangeId4ResOk>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelAttrs4 {
+    pub ca_headerpadsize: Count4,
+    pub ca_maxrequestsize: Count4,
+    pub ca_maxresponsesize: Count4,
+    pub ca_maxresponsesize_cached: Count4,
+    pub ca_maxoperations: Count4,
+    pub ca_maxrequests: Count4,
+    /// `uint32_t ca_rdma_ird<1>`, which has the wire form of an optional
+    pub ca_rdma_ird: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GssCbHandles4 {
+    pub gcbp_service: RpcGssSvc,
+    pub gcbp_handle_from_server: Gsshandle4,
+    pub gcbp_handle_from_client: Gsshandle4,
+}
+
+/// `callback_sec_parms4`, switched on the RPC auth flavor
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum CallbackSecParms4 {
+    AuthNone = 0,
+    AuthSys(AuthSysParams) = 1,
+    RpcsecGss(GssCbHandles4) = 6,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateSession4Args {
+    pub csa_clientid: Clientid4,
+    pub csa_sequence: Sequenceid4,
+    /// `CREATE_SESSION4_FLAG_*` bits
+    pub csa_flags: u32,
+    pub csa_fore_chan_attrs: ChannelAttrs4,
+    pub csa_back_chan_attrs: ChannelAttrs4,
+    pub csa_cb_program: u32,
+    pub csa_sec_parms: Vec<CallbackSecParms4>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateSession4ResOk {
+    pub csr_sessionid: Sessionid4,
+    pub csr_sequence: Sequenceid4,
+    pub csr_flags: u32,
+    pub csr_fore_chan_attrs: ChannelAttrs4,
+    pub csr_back_chan_attrs: ChannelAttrs4,
+}
+
+pub type CreateSession4Res = Nfs4Result<CreateSession4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestroySession4Args {
+    pub dsa_sessionid: Sessionid4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreeStateid4Args {
+    pub fsa_stateid: Stateid4,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum SecinfoStyle4 {
+    SecinfoStyle4CurrentFh = 0,
+    SecinfoStyle4Parent = 1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sequence4Args {
+    pub sa_sessionid: Sessionid4,
+    pub sa_sequenceid: Sequenceid4,
+    pub sa_slotid: Slotid4,
+    pub sa_highest_slotid: Slotid4,
+    pub sa_cachethis: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sequence4ResOk {
+    pub sr_sessionid: Sessionid4,
+    pub sr_sequenceid: Sequenceid4,
+    pub sr_slotid: Slotid4,
+    pub sr_highest_slotid: Slotid4,
+    pub sr_target_highest_slotid: Slotid4,
+    pub sr_status_flags: u32,
+}
+
+pub type Sequence4Res = Nfs4Result<Sequence4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestStateid4Args {
+    pub ts_stateids: Vec<Stateid4>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestStateid4ResOk {
+    pub tsr_status_codes: Vec<Nfsstat4>,
+}
+
+pub type TestStateid4Res = Nfs4Result<TestStateid4ResOk>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestroyClientid4Args {
+    pub dca_clientid: Clientid4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReclaimComplete4Args {
+    pub rca_one_fs: bool,
+}
+
+/// `nfs_argop4`, switched on `NfsOpnum4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum NfsArgop4 {
+    Access(Access4Args) = 3,
+    Close(Close4Args) = 4,
+    Commit(Commit4Args) = 5,
+    Create(Create4Args) = 6,
+    Delegpurge(Delegpurge4Args) = 7,
+    Delegreturn(Delegreturn4Args) = 8,
+    Getattr(Getattr4Args) = 9,
+    Getfh = 10,
+    Link(Link4Args) = 11,
+    Lock(Lock4Args) = 12,
+    Lockt(Lockt4Args) = 13,
+    Locku(Locku4Args) = 14,
+    Lookup(Lookup4Args) = 15,
+    Lookupp = 16,
+    Nverify(Nverify4Args) = 17,
+    Open(Open4Args) = 18,
+    Openattr(Openattr4Args) = 19,
+    OpenConfirm(OpenConfirm4Args) = 20,
+    OpenDowngrade(OpenDowngrade4Args) = 21,
+    Putfh(Putfh4Args) = 22,
+    Putpubfh = 23,
+    Putrootfh = 24,
+    Read(Read4Args) = 25,
+    Readdir(Readdir4Args) = 26,
+    Readlink = 27,
+    Remove(Remove4Args) = 28,
+    Rename(Rename4Args) = 29,
+    Renew(Renew4Args) = 30,
+    Restorefh = 31,
+    Savefh = 32,
+    Secinfo(Secinfo4Args) = 33,
+    Setattr(Setattr4Args) = 34,
+    Setclientid(Setclientid4Args) = 35,
+    SetclientidConfirm(SetclientidConfirm4Args) = 36,
+    Verify(Verify4Args) = 37,
+    Write(Write4Args) = 38,
+    ReleaseLockowner(ReleaseLockowner4Args) = 39,
+    BindConnToSession(BindConnToSession4Args) = 41,
+    ExchangeId(ExchangeId4Args) = 42,
+    CreateSession(CreateSession4Args) = 43,
+    DestroySession(DestroySession4Args) = 44,
+    FreeStateid(FreeStateid4Args) = 45,
+    SecinfoNoName(SecinfoStyle4) = 52,
+    Sequence(Sequence4Args) = 53,
+    TestStateid(TestStateid4Args) = 55,
+    DestroyClientid(DestroyClientid4Args) = 57,
+    ReclaimComplete(ReclaimComplete4Args) = 58,
+    Illegal = 10044,
+}
+
+/// `nfs_resop4`, switched on `NfsOpnum4`
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum NfsResop4 {
+    Access(Access4Res) = 3,
+    Close(Close4Res) = 4,
+    Commit(Commit4Res) = 5,
+    Create(Create4Res) = 6,
+    Delegpurge(Nfsstat4) = 7,
+    Delegreturn(Nfsstat4) = 8,
+    Getattr(Getattr4Res) = 9,
+    Getfh(Getfh4Res) = 10,
+    Link(Link4Res) = 11,
+    Lock(Lock4Res) = 12,
+    Lockt(Lockt4Res) = 13,
+    Locku(Locku4Res) = 14,
+    Lookup(Nfsstat4) = 15,
+    Lookupp(Nfsstat4) = 16,
+    Nverify(Nfsstat4) = 17,
+    Open(Open4Res) = 18,
+    Openattr(Nfsstat4) = 19,
+    OpenConfirm(OpenConfirm4Res) = 20,
+    OpenDowngrade(OpenDowngrade4Res) = 21,
+    Putfh(Nfsstat4) = 22,
+    Putpubfh(Nfsstat4) = 23,
+    Putrootfh(Nfsstat4) = 24,
+    Read(Read4Res) = 25,
+    Readdir(Readdir4Res) = 26,
+    Readlink(Readlink4Res) = 27,
+    Remove(Remove4Res) = 28,
+    Rename(Rename4Res) = 29,
+    Renew(Nfsstat4) = 30,
+    Restorefh(Nfsstat4) = 31,
+    Savefh(Nfsstat4) = 32,
+    Secinfo(Secinfo4Res) = 33,
+    Setattr(Setattr4Res) = 34,
+    Setclientid(Setclientid4Res) = 35,
+    SetclientidConfirm(Nfsstat4) = 36,
+    Verify(Nfsstat4) = 37,
+    Write(Write4Res) = 38,
+    ReleaseLockowner(Nfsstat4) = 39,
+    BindConnToSession(BindConnToSession4Res) = 41,
+    ExchangeId(ExchangeId4Res) = 42,
+    CreateSession(CreateSession4Res) = 43,
+    DestroySession(Nfsstat4) = 44,
+    FreeStateid(Nfsstat4) = 45,
+    SecinfoNoName(Secinfo4Res) = 52,
+    Sequence(Sequence4Res) = 53,
+    TestStateid(TestStateid4Res) = 55,
+    DestroyClientid(Nfsstat4) = 57,
+    ReclaimComplete(Nfsstat4) = 58,
+    Illegal(Nfsstat4) = 10044,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compound4Args {
+    pub tag: Utf8strCs,
+    pub minorversion: u32,
+    pub argarray: Vec<NfsArgop4>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compound4Res {
+    /// status of the last operation evaluated
+    pub status: Nfsstat4,
+    pub tag: Utf8strCs,
+    pub resarray: Vec<NfsResop4>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_bytes, to_bytes};
+
+    #[test]
+    fn test_bitmap4() {
+        let bitmap = Bitmap4::from_ids(&[FATTR4_TYPE, FATTR4_SIZE, FATTR4_MODE, FATTR4_OWNER]);
+        assert_eq!(bitmap.0, [0x0000_0012, 0x0000_0012]);
+        assert_eq!(
+            bitmap.iter().collect::<Vec<_>>(),
+            [FATTR4_TYPE, FATTR4_SIZE, FATTR4_MODE, FATTR4_OWNER]
+        );
+        assert!(bitmap.contains(FATTR4_MODE));
+        assert!(!bitmap.contains(FATTR4_FILEID));
+        assert!(!bitmap.contains(FATTR4_SUPPATTR_EXCLCREAT));
+        assert_eq!(
+            to_bytes(&bitmap).unwrap(),
+            [0, 0, 0, 2, 0, 0, 0, 0x12, 0, 0, 0, 0x12]
+        );
+        assert!(Bitmap4::from_ids(&[]).is_empty());
+        assert!(Bitmap4(vec![0, 0]).is_empty());
+    }
+
+    #[test]
+    fn test_fattr4_values() {
+        let fattr = Fattr4::new(vec![
+            AttrValue::Mode(0o644),
+            AttrValue::Size(1),
+            AttrValue::Type(NfsFtype4::Nf4reg),
+            AttrValue::Owner("root".to_string()),
+            AttrValue::Size(4096),
+        ])
+        .unwrap();
+        assert_eq!(fattr.attrmask.0, [0x0000_0012, 0x0000_0012]);
+        assert_eq!(
+            fattr.attr_vals[..],
+            [
+                0, 0, 0, 1, // type
+                0, 0, 0, 0, 0, 0, 0x10, 0, // size, the later value
+                0, 0, 0x01, 0xa4, // mode
+                0, 0, 0, 4, b'r', b'o', b'o', b't', // owner
+            ]
+        );
+        assert_eq!(
+            fattr.values().unwrap(),
+            [
+                AttrValue::Type(NfsFtype4::Nf4reg),
+                AttrValue::Size(4096),
+                AttrValue::Mode(0o644),
+                AttrValue::Owner("root".to_string()),
+            ]
+        );
+        assert_eq!(
+            from_bytes::<Fattr4>(&to_bytes(&fattr).unwrap()).unwrap(),
+            fattr
+        );
+
+        let fattr = Fattr4::new(vec![
+            AttrValue::SuppattrExclcreat(Bitmap4::from_ids(&[FATTR4_MODE])),
+            AttrValue::TimeModifySet(Settime4::SetToServerTime4),
+        ])
+        .unwrap();
+        assert_eq!(fattr.attrmask.iter().collect::<Vec<_>>(), [54, 75]);
+        assert_eq!(fattr.values().unwrap().len(), 2);
+
+        // the values following an unknown attribute cannot be found
+        let unknown = Fattr4 {
+            attrmask: Bitmap4::from_ids(&[FATTR4_SIZE, 62]),
+            attr_vals: vec![0; 12].try_into().unwrap(),
+        };
+        assert!(matches!(unknown.values(), Err(Error::UnknownAttr(62))));
+
+        let short = Fattr4 {
+            attrmask: Bitmap4::from_ids(&[FATTR4_SIZE]),
+            attr_vals: vec![0; 4].try_into().unwrap(),
+        };
+        assert!(short.values().is_err());
+        let long = Fattr4 {
+            attrmask: Bitmap4::from_ids(&[FATTR4_SIZE]),
+            attr_vals: vec![0; 12].try_into().unwrap(),
+        };
+        assert!(matches!(long.values(), Err(Error::TrailingBytes)));
+    }
+
+    #[test]
+    fn test_compound4() {
+        let args = Compound4Args {
+            tag: "t".to_string(),
+            minorversion: 0,
+            argarray: vec![
+                NfsArgop4::Putrootfh,
+                NfsArgop4::Lookup(Lookup4Args {
+                    objname: "a".to_string(),
+                }),
+                NfsArgop4::Getfh,
+                NfsArgop4::Getattr(Getattr4Args {
+                    attr_request: Bitmap4::from_ids(&[FATTR4_SIZE]),
+                }),
+            ],
+        };
+        let bytes = to_bytes(&args).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0, 0, 0, 1, b't', 0, 0, 0, // tag
+                0, 0, 0, 0, // minorversion
+                0, 0, 0, 4, // four operations
+                0, 0, 0, 24, // PUTROOTFH
+                0, 0, 0, 15, 0, 0, 0, 1, b'a', 0, 0, 0, // LOOKUP
+                0, 0, 0, 10, // GETFH
+                0, 0, 0, 9, 0, 0, 0, 1, 0, 0, 0, 0x10, // GETATTR
+            ]
+        );
+        assert_eq!(from_bytes::<Compound4Args>(&bytes).unwrap(), args);
+
+        let res = Compound4Res {
+            status: Nfsstat4::Nfs4errNoent,
+            tag: "t".to_string(),
+            resarray: vec![
+                NfsResop4::Putrootfh(Nfsstat4::Nfs4Ok),
+                NfsResop4::Getfh(Getfh4Res::Ok(Getfh4ResOk {
+                    object: vec![1, 2, 3].try_into().unwrap(),
+                })),
+                NfsResop4::Lookup(Nfsstat4::Nfs4errNoent),
+            ],
+        };
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(
+            bytes[12..],
+            [
+                0, 0, 0, 3, // three results
+                0, 0, 0, 24, 0, 0, 0, 0, // PUTROOTFH
+                0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 3, 1, 2, 3, 0, // GETFH
+                0, 0, 0, 15, 0, 0, 0, 2, // LOOKUP
+            ]
+        );
+        assert_eq!(from_bytes::<Compound4Res>(&bytes).unwrap(), res);
+
+        // GETFH failing has a void result
+        let res = NfsResop4::Getfh(Getfh4Res::Fail(Nfsstat4::Nfs4errNofilehandle));
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 10, 0, 0, 0x27, 0x24]);
+        assert_eq!(from_bytes::<NfsResop4>(&bytes).unwrap(), res);
+
+        // LAYOUTGET is not modeled
+        assert!(from_bytes::<NfsArgop4>(&[0, 0, 0, 50]).is_err());
+    }
+
+    #[test]
+    fn test_lock_and_setclientid() {
+        let owner = LockOwner4 {
+            clientid: 7,
+            owner: vec![1].try_into().unwrap(),
+        };
+        let args = NfsArgop4::Lock(Lock4Args {
+            locktype: NfsLockType4::WriteLt,
+            reclaim: false,
+            offset: 0,
+            length: u64::MAX,
+            locker: Locker4::OpenOwner(OpenToLockOwner4 {
+                open_seqid: 1,
+                open_stateid: Stateid4::anonymous(),
+                lock_seqid: 0,
+                lock_owner: owner.clone(),
+            }),
+        });
+        let bytes = to_bytes(&args).unwrap();
+        // new_lock_owner is TRUE
+        assert_eq!(bytes[28..32], [0, 0, 0, 1]);
+        assert_eq!(from_bytes::<NfsArgop4>(&bytes).unwrap(), args);
+
+        let res = NfsResop4::Lock(Lock4Res::Denied(Lock4Denied {
+            offset: 0,
+            length: 10,
+            locktype: NfsLockType4::ReadLt,
+            owner,
+        }));
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes[..8], [0, 0, 0, 12, 0, 0, 0x27, 0x1a]);
+        assert_eq!(from_bytes::<NfsResop4>(&bytes).unwrap(), res);
+        let res = NfsResop4::Lockt(Lockt4Res::Fail(10011));
+        assert_eq!(
+            from_bytes::<NfsResop4>(&to_bytes(&res).unwrap()).unwrap(),
+            res
+        );
+
+        let res = Setclientid4Res::ClidInuse(Netaddr4 {
+            na_r_netid: "tcp".to_string(),
+            na_r_addr: "192.0.2.1.3.232".to_string(),
+        });
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(from_bytes::<Setclientid4Res>(&bytes).unwrap(), res);
+        let Setclientid4Res::ClidInuse(addr) = res else {
+            unreachable!()
+        };
+        assert_eq!(
+            addr.to_socket_addr().unwrap(),
+            "192.0.2.1:1000".parse::<SocketAddr>().unwrap()
+        );
+        let mut local = to_bytes(&Nfsstat4::Nfs4errClidInuse).unwrap();
+        local.extend(to_bytes(&("local", "/run/nfs.sock")).unwrap());
+        let Setclientid4Res::ClidInuse(addr) = from_bytes(&local).unwrap() else {
+            panic!("expected ClidInuse");
+        };
+        assert_eq!(addr.na_r_addr, "/run/nfs.sock");
+        assert!(matches!(
+            addr.to_socket_addr(),
+            Err(Error::InvalidUniversalAddr(_))
+        ));
+    }
+
+    #[test]
+    fn test_session_operations() {
+        let args = Compound4Args {
+            tag: String::new(),
+            minorversion: 1,
+            argarray: vec![NfsArgop4::ExchangeId(ExchangeId4Args {
+                eia_clientowner: ClientOwner4 {
+                    co_verifier: FixedLengthBytes::new([1; 8]),
+                    co_ownerid: b"client".to_vec().try_into().unwrap(),
+                },
+                eia_flags: EXCHGID4_FLAG_SUPP_MOVED_REFER,
+                eia_state_protect: StateProtect4A::Sp4None,
+                eia_client_impl_id: Some(NfsImplId4 {
+                    nii_domain: "example.com".to_string(),
+                    nii_name: "xdr_brk".to_string(),
+                    nii_date: Nfstime4::default(),
+                }),
+            })],
+        };
+        assert_eq!(
+            from_bytes::<Compound4Args>(&to_bytes(&args).unwrap()).unwrap(),
+            args
+        );
+
+        let sessionid = FixedLengthBytes::new([9; NFS4_SESSIONID_SIZE]);
+        let args = Compound4Args {
+            tag: String::new(),
+            minorversion: 1,
+            argarray: vec![
+                NfsArgop4::Sequence(Sequence4Args {
+                    sa_sessionid: sessionid.clone(),
+                    sa_sequenceid: 1,
+                    sa_slotid: 0,
+                    sa_highest_slotid: 0,
+                    sa_cachethis: false,
+                }),
+                NfsArgop4::CreateSession(CreateSession4Args {
+                    csa_clientid: 1,
+                    csa_sequence: 1,
+                    csa_flags: CREATE_SESSION4_FLAG_CONN_BACK_CHAN,
+                    csa_fore_chan_attrs: ChannelAttrs4::default(),
+                    csa_back_chan_attrs: ChannelAttrs4 {
+                        ca_rdma_ird: Some(1),
+                        ..Default::default()
+                    },
+                    csa_cb_program: 0x4000_0000,
+                    csa_sec_parms: vec![
+                        CallbackSecParms4::AuthNone,
+                        CallbackSecParms4::AuthSys(AuthSysParams::default()),
+                    ],
+                }),
+                NfsArgop4::ReclaimComplete(ReclaimComplete4Args { rca_one_fs: false }),
+            ],
+        };
+        assert_eq!(
+            from_bytes::<Compound4Args>(&to_bytes(&args).unwrap()).unwrap(),
+            args
+        );
+
+        let res = NfsResop4::Sequence(Sequence4Res::Ok(Sequence4ResOk {
+            sr_sessionid: sessionid,
+            sr_sequenceid: 1,
+            sr_slotid: 0,
+            sr_highest_slotid: 63,
+            sr_target_highest_slotid: 63,
+            sr_status_flags: 0,
+        }));
+        let bytes = to_bytes(&res).unwrap();
+        assert_eq!(bytes.len(), 4 + 4 + 16 + 5 * 4);
+        assert_eq!(from_bytes::<NfsResop4>(&bytes).unwrap(), res);
+    }
+}
@@ -48,6 +48,166 @@ impl<'de, const N: usize> Deserialize<'de> for FixedLengthBytes<N> {
     }
 }
 
+/// Variable-length opaque data `opaque<MAX>`, holding at most `MAX` bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct VariableLengthBytes<const MAX: usize = { u32::MAX as usize }> {
+    bytes: Vec<u8>,
+}
+
+impl<const MAX: usize> VariableLengthBytes<MAX> {
+    /// fails with `Error::LimitExceeded` if `bytes` is longer than `MAX`
+    pub fn new(bytes: Vec<u8>) -> crate::Result<Self> {
+        crate::limits::check_limit(crate::LimitKind::BytesLen, MAX, bytes.len())?;
+        Ok(Self { bytes })
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<const MAX: usize> Deref for VariableLengthBytes<MAX> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl<const MAX: usize> TryFrom<Vec<u8>> for VariableLengthBytes<MAX> {
+    type Error = crate::Error;
+    fn try_from(bytes: Vec<u8>) -> crate::Result<Self> {
+        Self::new(bytes)
+    }
+}
+
+impl<const MAX: usize> Serialize for VariableLengthBytes<MAX> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(&self.bytes)
+    }
+}
+
+impl<'de, const MAX: usize> Deserialize<'de> for VariableLengthBytes<MAX> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor<const MAX: usize>;
+
+        impl<const MAX: usize> serde::de::Visitor<'_> for Visitor<MAX> {
+            type Value = VariableLengthBytes<MAX>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "at most {} bytes", MAX)
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                self.visit_byte_buf(v.to_vec())
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                if v.len() > MAX {
+                    return Err(E::invalid_length(v.len(), &self));
+                }
+                Ok(VariableLengthBytes { bytes: v })
+            }
+        }
+
+        deserializer.deserialize_byte_buf(Visitor::<MAX>)
+    }
+}
+
+/// Variable-length string `string<MAX>`, holding at most `MAX` bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct VariableLengthString<const MAX: usize = { u32::MAX as usize }> {
+    string: String,
+}
+
+impl<const MAX: usize> VariableLengthString<MAX> {
+    /// fails with `Error::LimitExceeded` if `string` is longer than `MAX` bytes
+    pub fn new(string: String) -> crate::Result<Self> {
+        crate::limits::check_limit(crate::LimitKind::BytesLen, MAX, string.len())?;
+        Ok(Self { string })
+    }
+
+    pub fn into_inner(self) -> String {
+        self.string
+    }
+}
+
+impl<const MAX: usize> Deref for VariableLengthString<MAX> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
+
+impl<const MAX: usize> TryFrom<String> for VariableLengthString<MAX> {
+    type Error = crate::Error;
+    fn try_from(string: String) -> crate::Result<Self> {
+        Self::new(string)
+    }
+}
+
+impl<const MAX: usize> TryFrom<&str> for VariableLengthString<MAX> {
+    type Error = crate::Error;
+    fn try_from(string: &str) -> crate::Result<Self> {
+        Self::new(string.to_string())
+    }
+}
+
+impl<const MAX: usize> std::fmt::Display for VariableLengthString<MAX> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.string)
+    }
+}
+
+impl<const MAX: usize> Serialize for VariableLengthString<MAX> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.string)
+    }
+}
+
+impl<'de, const MAX: usize> Deserialize<'de> for VariableLengthString<MAX> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor<const MAX: usize>;
+
+        impl<const MAX: usize> serde::de::Visitor<'_> for Visitor<MAX> {
+            type Value = VariableLengthString<MAX>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a string of at most {} bytes", MAX)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                if v.len() > MAX {
+                    return Err(E::invalid_length(v.len(), &self));
+                }
+                Ok(VariableLengthString {
+                    string: v.to_string(),
+                })
+            }
+
+            fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
+                if v.len() > MAX {
+                    return Err(E::invalid_length(v.len(), &self));
+                }
+                Ok(VariableLengthString { string: v })
+            }
+        }
+
+        deserializer.deserialize_string(Visitor::<MAX>)
+    }
+}
+
 #[test]
 fn test_fixed_length_bytes() {
     use crate::{from_bytes, to_bytes};
@@ -58,3 +218,48 @@ fn test_fixed_length_bytes() {
     let deserialized_bytes: FixedLengthBytes<3> = from_bytes(&serialized_bytes).unwrap();
     assert_eq!(deserialized_bytes, fixed_length_bytes);
 }
+
+#[test]
+fn test_variable_length_bytes() {
+    use crate::{Error, LimitKind, from_bytes, to_bytes};
+    let bytes = VariableLengthBytes::<4>::new(vec![1, 2, 3]).unwrap();
+    let serialized_bytes = to_bytes(&bytes).unwrap();
+    assert_eq!(serialized_bytes, &[0, 0, 0, 3, 1, 2, 3, 0]);
+    let deserialized_bytes: VariableLengthBytes<4> = from_bytes(&serialized_bytes).unwrap();
+    assert_eq!(deserialized_bytes, bytes);
+
+    let result = from_bytes::<VariableLengthBytes<2>>(&serialized_bytes);
+    assert!(result.is_err());
+    let result = VariableLengthBytes::<2>::new(vec![1, 2, 3]);
+    assert!(matches!(
+        result,
+        Err(Error::LimitExceeded {
+            kind: LimitKind::BytesLen,
+            limit: 2,
+            actual: 3,
+        })
+    ));
+}
+
+#[test]
+fn test_variable_length_string() {
+    use crate::{Error, LimitKind, from_bytes, to_bytes};
+    let string = VariableLengthString::<4>::try_from("abc").unwrap();
+    let serialized_bytes = to_bytes(&string).unwrap();
+    assert_eq!(serialized_bytes, &[0, 0, 0, 3, b'a', b'b', b'c', 0]);
+    let deserialized_string: VariableLengthString<4> = from_bytes(&serialized_bytes).unwrap();
+    assert_eq!(deserialized_string, string);
+    assert_eq!(&*deserialized_string, "abc");
+
+    let result = from_bytes::<VariableLengthString<2>>(&serialized_bytes);
+    assert!(result.is_err());
+    let result = VariableLengthString::<2>::try_from("abc");
+    assert!(matches!(
+        result,
+        Err(Error::LimitExceeded {
+            kind: LimitKind::BytesLen,
+            limit: 2,
+            actual: 3,
+        })
+    ));
+}
@@ -0,0 +1,176 @@
+use serde::de::{self, SeqAccess};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// Linked list declared in XDR through optional-data, such as
+/// `struct node { T item; node *next; }`, held as a `Vec`.
+///
+/// On the wire every element is preceded by a `TRUE` optional discriminant
+/// and the list ends with `FALSE`, e.g. `1 T 1 T 0`. Unlike a recursive
+/// `Option<Box<Node>>`, neither encoding nor decoding nests once per element,
+/// so long lists do not run into the depth limit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OptionalList<T>(pub Vec<T>);
+
+impl<T> OptionalList<T> {
+    pub fn new() -> Self {
+        OptionalList(Vec::new())
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> Default for OptionalList<T> {
+    fn default() -> Self {
+        OptionalList::new()
+    }
+}
+
+impl<T> Deref for OptionalList<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for OptionalList<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<T>> for OptionalList<T> {
+    fn from(items: Vec<T>) -> Self {
+        OptionalList(items)
+    }
+}
+
+impl<T> FromIterator<T> for OptionalList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        OptionalList(iter.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for OptionalList<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<T: Serialize> Serialize for OptionalList<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2 * self.0.len() + 1)?;
+        for item in &self.0 {
+            tuple.serialize_element(&true)?;
+            tuple.serialize_element(item)?;
+        }
+        tuple.serialize_element(&false)?;
+        tuple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OptionalList<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for Visitor<T> {
+            type Value = OptionalList<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("optional-data linked list")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                // the length is not known up front, so do not trust size_hint
+                let mut items = Vec::new();
+                loop {
+                    let more: bool = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(2 * items.len(), &self))?;
+                    if !more {
+                        return Ok(OptionalList(items));
+                    }
+                    let item = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(2 * items.len() + 1, &self))?;
+                    items.push(item);
+                }
+            }
+        }
+
+        deserializer.deserialize_tuple(usize::MAX, Visitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::OptionalList;
+    use crate::{RECOMMENDED_MAX_DEPTH, from_bytes, to_bytes};
+
+    #[test]
+    fn test_optional_list() {
+        let list: OptionalList<u32> = vec![7, 8].into();
+        let bytes = to_bytes(&list).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0, 0, 0, 1, 0, 0, 0, 7, // first element
+                0, 0, 0, 1, 0, 0, 0, 8, // second element
+                0, 0, 0, 0, // end of list
+            ]
+        );
+        assert_eq!(from_bytes::<OptionalList<u32>>(&bytes).unwrap(), list);
+
+        let empty = OptionalList::<u32>::new();
+        assert_eq!(to_bytes(&empty).unwrap(), [0, 0, 0, 0]);
+        assert_eq!(
+            from_bytes::<OptionalList<u32>>(&[0, 0, 0, 0]).unwrap(),
+            empty
+        );
+
+        assert!(from_bytes::<OptionalList<u32>>(&bytes[..16]).is_err());
+        assert!(from_bytes::<OptionalList<u32>>(&[0, 0, 0, 2]).is_err());
+    }
+
+    #[test]
+    fn test_optional_list_same_wire_as_recursion() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Node {
+            item: u32,
+            next: Option<Box<Node>>,
+        }
+
+        let recursive = Some(Box::new(Node {
+            item: 1,
+            next: Some(Box::new(Node {
+                item: 2,
+                next: None,
+            })),
+        }));
+        let list: OptionalList<u32> = vec![1, 2].into();
+        assert_eq!(to_bytes(&recursive).unwrap(), to_bytes(&list).unwrap());
+
+        // far longer than a recursive list could be within the depth limit
+        let long: OptionalList<u32> = (0..RECOMMENDED_MAX_DEPTH as u32 * 4).collect();
+        let bytes = to_bytes(&long).unwrap();
+        assert_eq!(from_bytes::<OptionalList<u32>>(&bytes).unwrap(), long);
+    }
+}
@@ -0,0 +1,220 @@
+//! Input sources for [`XDRDeserializer`](crate::XDRDeserializer).
+//!
+//! `SliceRead` decodes from an in-memory buffer and lets strings borrow from it,
+//! `IoRead` pulls exactly the bytes each value needs from any `std::io::Read`.
+
+use std::io;
+use std::ops::Deref;
+
+use crate::error::{Error, Result};
+
+/// Bytes handed out by [`Read::read_slice`], either borrowed from the input
+/// for `'b` or copied into the deserializer's scratch buffer for `'c`.
+pub enum Reference<'b, 'c, T: ?Sized> {
+    Borrowed(&'b T),
+    Copied(&'c T),
+}
+
+impl<'b, 'c, T: ?Sized> Deref for Reference<'b, 'c, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Reference::Borrowed(b) => b,
+            Reference::Copied(c) => c,
+        }
+    }
+}
+
+pub trait Read<'de> {
+    /// fill `buf` completely, failing with `Error::EndOfFile` when the input runs out
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()>;
+
+    /// read `len` bytes, borrowing them from the input when possible,
+    /// otherwise copying them into `scratch`
+    fn read_slice<'s>(
+        &mut self,
+        len: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>>;
+
+    /// consume `len` bytes without keeping them, failing with
+    /// `Error::EndOfFile` when the input runs out
+    fn skip(&mut self, len: usize) -> Result<()> {
+        let mut buf = [0u8; 512];
+        let mut rest = len;
+        while rest > 0 {
+            let chunk = rest.min(buf.len());
+            self.read_exact(&mut buf[..chunk])?;
+            rest -= chunk;
+        }
+        Ok(())
+    }
+
+    /// number of bytes consumed so far
+    fn position(&self) -> usize;
+
+    /// whether all input has been consumed
+    fn is_empty(&mut self) -> Result<bool>;
+}
+
+#[derive(Debug)]
+pub struct SliceRead<'de> {
+    slice: &'de [u8],
+    index: usize,
+}
+
+impl<'de> SliceRead<'de> {
+    pub fn new(slice: &'de [u8]) -> Self {
+        SliceRead { slice, index: 0 }
+    }
+
+    /// input not consumed yet
+    pub fn remaining(&self) -> &'de [u8] {
+        &self.slice[self.index..]
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        let end = self.index.checked_add(len).ok_or(Error::EndOfFile)?;
+        let bytes = self.slice.get(self.index..end).ok_or(Error::EndOfFile)?;
+        self.index = end;
+        Ok(bytes)
+    }
+}
+
+impl<'de> Read<'de> for SliceRead<'de> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        buf.copy_from_slice(self.take(buf.len())?);
+        Ok(())
+    }
+
+    fn read_slice<'s>(
+        &mut self,
+        len: usize,
+        _scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>> {
+        self.take(len).map(Reference::Borrowed)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len)?;
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.index
+    }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        Ok(self.index == self.slice.len())
+    }
+}
+
+#[derive(Debug)]
+pub struct IoRead<R> {
+    reader: R,
+    position: usize,
+    peeked: Option<u8>,
+}
+
+impl<R: io::Read> IoRead<R> {
+    pub fn new(reader: R) -> Self {
+        IoRead {
+            reader,
+            position: 0,
+            peeked: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+pub(crate) fn io_error(err: io::Error) -> Error {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        Error::EndOfFile
+    } else {
+        Error::Io(err)
+    }
+}
+
+impl<'de, R: io::Read> Read<'de> for IoRead<R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let start = match self.peeked.take() {
+            Some(byte) if !buf.is_empty() => {
+                buf[0] = byte;
+                1
+            }
+            peeked => {
+                self.peeked = peeked;
+                0
+            }
+        };
+        self.reader
+            .read_exact(&mut buf[start..])
+            .map_err(io_error)?;
+        self.position += buf.len();
+        Ok(())
+    }
+
+    fn read_slice<'s>(
+        &mut self,
+        len: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>> {
+        scratch.clear();
+        if len > 0
+            && let Some(byte) = self.peeked.take()
+        {
+            scratch.push(byte);
+        }
+        // read through `take` instead of resizing up front, so a bogus length
+        // prefix fails with EndOfFile rather than a huge allocation
+        let rest = (len - scratch.len()) as u64;
+        let mut limited = io::Read::take(&mut self.reader, rest);
+        io::Read::read_to_end(&mut limited, scratch).map_err(io_error)?;
+        self.position += scratch.len();
+        if scratch.len() < len {
+            return Err(Error::EndOfFile);
+        }
+        Ok(Reference::Copied(&scratch[..]))
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        let mut rest = len;
+        if rest > 0 && self.peeked.take().is_some() {
+            rest -= 1;
+            self.position += 1;
+        }
+        let mut limited = io::Read::take(&mut self.reader, rest as u64);
+        let skipped = io::copy(&mut limited, &mut io::sink()).map_err(io_error)? as usize;
+        self.position += skipped;
+        if skipped < rest {
+            return Err(Error::EndOfFile);
+        }
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        if self.peeked.is_some() {
+            return Ok(false);
+        }
+        let mut byte = [0u8; 1];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(true),
+                Ok(_) => {
+                    self.peeked = Some(byte[0]);
+                    return Ok(false);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+    }
+}
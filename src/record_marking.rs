@@ -0,0 +1,293 @@
+//! Record marking for XDR over stream transports (RFC 5531 section 11).
+//!
+//! A record is sent as one or more fragments, each preceded by a 4-byte mark
+//! holding the fragment length in its low 31 bits and, in its high bit,
+//! whether the fragment is the last one of the record.
+
+use std::io;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, LimitKind, Result};
+use crate::limits::check_limit;
+use crate::read::io_error;
+use crate::{U32_SIZE, from_bytes, to_bytes_into};
+
+/// high bit of a record mark, set on the last fragment of a record
+pub const LAST_FRAGMENT: u32 = 0x8000_0000;
+
+/// longest fragment a record mark can describe
+pub const MAX_FRAGMENT_LEN: usize = 0x7fff_ffff;
+
+/// largest record `RecordReader`, and with it the RPC server and client,
+/// accepts unless told otherwise, room for 1 MiB of NFS READ or WRITE data
+/// and the headers around it
+pub const DEFAULT_MAX_RECORD_LEN: usize = (1 << 20) + 64 * 1024;
+
+/// Writes whole records as fragments of at most `max_fragment_len` bytes.
+#[derive(Debug)]
+pub struct RecordWriter<W> {
+    writer: W,
+    max_fragment_len: usize,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> RecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        RecordWriter {
+            writer,
+            max_fragment_len: MAX_FRAGMENT_LEN,
+            buf: Vec::new(),
+        }
+    }
+
+    /// split records into fragments of at most `len` bytes, failing with
+    /// `Error::InvalidFragmentLen` if `len` is zero or larger than `MAX_FRAGMENT_LEN`
+    pub fn with_max_fragment_len(mut self, len: usize) -> Result<Self> {
+        if !(1..=MAX_FRAGMENT_LEN).contains(&len) {
+            return Err(Error::InvalidFragmentLen(len));
+        }
+        self.max_fragment_len = len;
+        Ok(self)
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// write `record` as one or more fragments; an empty record is sent as
+    /// a single empty last fragment
+    pub fn write_record(&mut self, record: &[u8]) -> Result<()> {
+        let mut fragments = record.chunks(self.max_fragment_len).peekable();
+        if fragments.peek().is_none() {
+            return self.write_fragment(&[], true);
+        }
+        while let Some(fragment) = fragments.next() {
+            self.write_fragment(fragment, fragments.peek().is_none())?;
+        }
+        Ok(())
+    }
+
+    /// encode `value` and write it as one record
+    pub fn write_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.clear();
+        let result = to_bytes_into(value, &mut buf).and_then(|()| self.write_record(&buf));
+        self.buf = buf;
+        result
+    }
+
+    fn write_fragment(&mut self, fragment: &[u8], last: bool) -> Result<()> {
+        let mut mark = fragment.len() as u32;
+        if last {
+            mark |= LAST_FRAGMENT;
+        }
+        self.writer.write_all(&mark.to_be_bytes())?;
+        self.writer.write_all(fragment)?;
+        Ok(())
+    }
+}
+
+/// Reassembles records from their fragments, refusing records longer than
+/// `max_record_len`, `DEFAULT_MAX_RECORD_LEN` by default.
+#[derive(Debug)]
+pub struct RecordReader<R> {
+    reader: R,
+    max_record_len: usize,
+    buf: Vec<u8>,
+}
+
+impl<R: io::Read> RecordReader<R> {
+    pub fn new(reader: R) -> Self {
+        RecordReader {
+            reader,
+            max_record_len: DEFAULT_MAX_RECORD_LEN,
+            buf: Vec::new(),
+        }
+    }
+
+    /// fail with `Error::LimitExceeded` on records longer than `len` bytes,
+    /// or never for `usize::MAX`
+    pub fn with_max_record_len(mut self, len: usize) -> Self {
+        self.max_record_len = len;
+        self
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// read the next whole record, or `None` if the stream ends cleanly before it
+    pub fn read_record(&mut self) -> Result<Option<&[u8]>> {
+        self.buf.clear();
+        let mut first = true;
+        loop {
+            let Some(mark) = self.read_mark(first)? else {
+                return Ok(None);
+            };
+            first = false;
+            let len = (mark & !LAST_FRAGMENT) as usize;
+            let total = self.buf.len().saturating_add(len);
+            check_limit(LimitKind::RecordLen, self.max_record_len, total)?;
+            // read through `take` so a bogus mark cannot force a huge allocation
+            let mut fragment = io::Read::take(&mut self.reader, len as u64);
+            io::Read::read_to_end(&mut fragment, &mut self.buf).map_err(io_error)?;
+            if self.buf.len() < total {
+                return Err(Error::EndOfFile);
+            }
+            if mark & LAST_FRAGMENT != 0 {
+                return Ok(Some(&self.buf));
+            }
+        }
+    }
+
+    /// read the next record and decode it as a single value, which must
+    /// take up the whole record
+    pub fn read_value<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        match self.read_record()? {
+            Some(record) => from_bytes(record).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// read a record mark, returning `None` on a clean end of stream when
+    /// `at_record_start`
+    fn read_mark(&mut self, at_record_start: bool) -> Result<Option<u32>> {
+        let mut mark = [0u8; U32_SIZE];
+        let mut filled = 0;
+        while filled < mark.len() {
+            match self.reader.read(&mut mark[filled..]) {
+                Ok(0) if filled == 0 && at_record_start => return Ok(None),
+                Ok(0) => return Err(Error::EndOfFile),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+        Ok(Some(u32::from_be_bytes(mark)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::{
+        DEFAULT_MAX_RECORD_LEN, LAST_FRAGMENT, MAX_FRAGMENT_LEN, RecordReader, RecordWriter,
+    };
+    use crate::{Error, LimitKind};
+
+    #[test]
+    fn test_record_marking_fragments() {
+        let mut writer = RecordWriter::new(Vec::new())
+            .with_max_fragment_len(4)
+            .unwrap();
+        writer.write_record(&[1, 2, 3, 4, 5, 6]).unwrap();
+        writer.write_record(&[]).unwrap();
+        let wire = writer.into_inner();
+        assert_eq!(
+            wire,
+            [
+                0, 0, 0, 4, 1, 2, 3, 4, // first fragment
+                0x80, 0, 0, 2, 5, 6, // last fragment
+                0x80, 0, 0, 0, // empty record
+            ]
+        );
+
+        let mut reader = RecordReader::new(&wire[..]);
+        assert_eq!(reader.read_record().unwrap(), Some(&[1, 2, 3, 4, 5, 6][..]));
+        assert_eq!(reader.read_record().unwrap(), Some(&[][..]));
+        assert_eq!(reader.read_record().unwrap(), None);
+    }
+
+    #[test]
+    fn test_record_marking_values() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Call {
+            xid: u32,
+            name: String,
+        }
+
+        let calls = [
+            Call {
+                xid: 1,
+                name: "lookup".to_string(),
+            },
+            Call {
+                xid: 2,
+                name: "read".to_string(),
+            },
+        ];
+        let mut writer = RecordWriter::new(Vec::new())
+            .with_max_fragment_len(8)
+            .unwrap();
+        for call in &calls {
+            writer.write_value(call).unwrap();
+        }
+        let wire = writer.into_inner();
+
+        let mut reader = RecordReader::new(&wire[..]);
+        let mut decoded = Vec::new();
+        while let Some(call) = reader.read_value::<Call>().unwrap() {
+            decoded.push(call);
+        }
+        assert_eq!(decoded, calls);
+    }
+
+    #[test]
+    fn test_record_marking_errors() {
+        let mut wire = (LAST_FRAGMENT | 8).to_be_bytes().to_vec();
+        wire.extend_from_slice(&[0; 8]);
+
+        let mut reader = RecordReader::new(&wire[..]).with_max_record_len(4);
+        let err = reader.read_record().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                kind: LimitKind::RecordLen,
+                limit: 4,
+                actual: 8,
+            }
+        ));
+
+        let mut reader = RecordReader::new(&wire[..6]);
+        assert!(matches!(reader.read_record(), Err(Error::EndOfFile)));
+
+        let mut reader = RecordReader::new(&wire[..2]);
+        assert!(matches!(reader.read_record(), Err(Error::EndOfFile)));
+
+        // a record without its last fragment is truncated, not empty
+        let mut reader = RecordReader::new(&[0, 0, 0, 0][..]);
+        assert!(matches!(reader.read_record(), Err(Error::EndOfFile)));
+
+        // a peer announcing a record far longer than it sends
+        let mut reader = RecordReader::new(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0][..]);
+        assert!(matches!(
+            reader.read_record(),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::RecordLen,
+                limit: DEFAULT_MAX_RECORD_LEN,
+                ..
+            })
+        ));
+
+        for len in [0, MAX_FRAGMENT_LEN + 1] {
+            assert!(matches!(
+                RecordWriter::new(Vec::new()).with_max_fragment_len(len),
+                Err(Error::InvalidFragmentLen(l)) if l == len
+            ));
+        }
+    }
+}
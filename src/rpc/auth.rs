@@ -0,0 +1,197 @@
+//! Typed credentials carried in the body of an `OpaqueAuth` (RFC 5531 appendix A).
+
+use serde::{Deserialize, Serialize};
+
+use super::{AuthFlavor, CallBody, OpaqueAuth};
+use crate::error::{Error, LimitKind, Result};
+use crate::limits::check_limit;
+use crate::{DecodeLimits, VariableLengthBytes, XDRDeserializer, to_bytes};
+
+/// maximum length of `AuthSysParams::machinename`
+pub const MAX_MACHINE_NAME_LEN: usize = 255;
+
+/// maximum number of `AuthSysParams::gids`
+pub const MAX_GIDS: usize = 16;
+
+/// `AUTH_NONE`, whose body carries nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AuthNone;
+
+impl From<AuthNone> for OpaqueAuth {
+    fn from(_: AuthNone) -> Self {
+        OpaqueAuth::none()
+    }
+}
+
+impl TryFrom<&OpaqueAuth> for AuthNone {
+    type Error = Error;
+
+    /// the body is not checked, RFC 5531 leaves it undefined
+    fn try_from(auth: &OpaqueAuth) -> Result<Self> {
+        match auth.flavor {
+            AuthFlavor::AuthNone => Ok(AuthNone),
+            flavor => Err(Error::UnexpectedAuthFlavor(flavor.into())),
+        }
+    }
+}
+
+/// `AUTH_SYS` credential, the caller's identity as claimed by its host.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthSysParams {
+    pub stamp: u32,
+    /// at most `MAX_MACHINE_NAME_LEN` bytes
+    pub machinename: String,
+    pub uid: u32,
+    pub gid: u32,
+    /// at most `MAX_GIDS` supplementary groups
+    pub gids: Vec<u32>,
+}
+
+impl AuthSysParams {
+    /// limits of `auth_sys_parms`, applied while decoding a body
+    const LIMITS: DecodeLimits = DecodeLimits {
+        max_seq_len: MAX_GIDS,
+        max_bytes_len: MAX_MACHINE_NAME_LEN,
+        ..DecodeLimits::UNLIMITED
+    };
+}
+
+impl TryFrom<&AuthSysParams> for OpaqueAuth {
+    type Error = Error;
+
+    /// fails with `Error::LimitExceeded` when the machine name, the gids
+    /// or the encoded body are too long
+    fn try_from(params: &AuthSysParams) -> Result<Self> {
+        check_limit(
+            LimitKind::BytesLen,
+            MAX_MACHINE_NAME_LEN,
+            params.machinename.len(),
+        )?;
+        check_limit(LimitKind::SeqLen, MAX_GIDS, params.gids.len())?;
+        Ok(OpaqueAuth {
+            flavor: AuthFlavor::AuthSys,
+            body: VariableLengthBytes::new(to_bytes(params)?)?,
+        })
+    }
+}
+
+impl TryFrom<&OpaqueAuth> for AuthSysParams {
+    type Error = Error;
+
+    /// decode the body of an `AUTH_SYS` credential, which must hold nothing else
+    fn try_from(auth: &OpaqueAuth) -> Result<Self> {
+        if auth.flavor != AuthFlavor::AuthSys {
+            return Err(Error::UnexpectedAuthFlavor(auth.flavor.into()));
+        }
+        let mut deserializer = XDRDeserializer::from_bytes(&auth.body).with_limits(Self::LIMITS);
+        let params = AuthSysParams::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(params)
+    }
+}
+
+impl CallBody {
+    /// set `params` as the credential, with an `AUTH_NONE` verifier
+    pub fn with_auth_sys(mut self, params: &AuthSysParams) -> Result<Self> {
+        self.cred = params.try_into()?;
+        self.verf = OpaqueAuth::none();
+        Ok(self)
+    }
+
+    /// the `AUTH_SYS` credential of this call
+    pub fn auth_sys(&self) -> Result<AuthSysParams> {
+        AuthSysParams::try_from(&self.cred)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::MAX_AUTH_BYTES;
+
+    fn params() -> AuthSysParams {
+        AuthSysParams {
+            stamp: 0x5f5e_1000,
+            machinename: "host".to_string(),
+            uid: 1000,
+            gid: 100,
+            gids: vec![100, 4],
+        }
+    }
+
+    #[test]
+    fn test_auth_sys_round_trip() {
+        let auth = OpaqueAuth::try_from(&params()).unwrap();
+        assert_eq!(auth.flavor, AuthFlavor::AuthSys);
+        assert_eq!(
+            &auth.body[..],
+            [
+                0x5f, 0x5e, 0x10, 0, // stamp
+                0, 0, 0, 4, b'h', b'o', b's', b't', // machinename
+                0, 0, 0x03, 0xe8, // uid
+                0, 0, 0, 100, // gid
+                0, 0, 0, 2, 0, 0, 0, 100, 0, 0, 0, 4, // gids
+            ]
+        );
+        assert_eq!(AuthSysParams::try_from(&auth).unwrap(), params());
+
+        let call = CallBody::new(100003, 3, 0)
+            .with_auth_sys(&params())
+            .unwrap();
+        assert_eq!(call.auth_sys().unwrap().uid, 1000);
+        assert!(AuthNone::try_from(&call.verf).is_ok());
+    }
+
+    #[test]
+    fn test_auth_sys_limits() {
+        let mut too_many_gids = params();
+        too_many_gids.gids = vec![0; MAX_GIDS + 1];
+        let result = OpaqueAuth::try_from(&too_many_gids);
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::SeqLen,
+                ..
+            })
+        ));
+
+        let mut long_name = params();
+        long_name.machinename = "h".repeat(MAX_MACHINE_NAME_LEN + 1);
+        assert!(OpaqueAuth::try_from(&long_name).is_err());
+
+        // a name of 255 bytes and 16 gids still fit in MAX_AUTH_BYTES
+        let mut largest = params();
+        largest.machinename = "h".repeat(MAX_MACHINE_NAME_LEN);
+        largest.gids = vec![0; MAX_GIDS];
+        let auth = OpaqueAuth::try_from(&largest).unwrap();
+        assert!(auth.body.len() <= MAX_AUTH_BYTES);
+
+        // a peer sending 17 gids is refused while decoding
+        let mut body = to_bytes(&too_many_gids).unwrap();
+        body.truncate(MAX_AUTH_BYTES);
+        let auth = OpaqueAuth {
+            flavor: AuthFlavor::AuthSys,
+            body: VariableLengthBytes::new(body).unwrap(),
+        };
+        let result = AuthSysParams::try_from(&auth);
+        assert!(matches!(
+            result.as_ref().map_err(Error::inner),
+            Err(Error::LimitExceeded {
+                kind: LimitKind::SeqLen,
+                limit: MAX_GIDS,
+                actual: 17,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_auth_flavor_mismatch() {
+        let result = AuthSysParams::try_from(&OpaqueAuth::none());
+        assert!(matches!(result, Err(Error::UnexpectedAuthFlavor(0))));
+        let auth = OpaqueAuth::try_from(&params()).unwrap();
+        assert!(matches!(
+            AuthNone::try_from(&auth),
+            Err(Error::UnexpectedAuthFlavor(1))
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+//! Duplicate request cache, replaying the replies of non-idempotent calls
+//! that a client sent again because it did not get the first reply in time.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+
+/// xid, client address, program and procedure of a call
+pub(crate) type RequestKey = (u32, SocketAddr, u32, u32);
+
+/// Encoded replies of the most recent calls, evicting the oldest first.
+#[derive(Debug)]
+pub(crate) struct ReplyCache {
+    capacity: usize,
+    replies: HashMap<RequestKey, Vec<u8>>,
+    order: VecDeque<RequestKey>,
+}
+
+impl ReplyCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        ReplyCache {
+            capacity,
+            replies: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub(crate) fn get(&self, key: &RequestKey) -> Option<&[u8]> {
+        self.replies.get(key).map(Vec::as_slice)
+    }
+
+    pub(crate) fn insert(&mut self, key: RequestKey, reply: Vec<u8>) {
+        if self.replies.insert(key, reply).is_none() {
+            self.order.push_back(key);
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.replies.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_cache_eviction() {
+        let addr: SocketAddr = ([127, 0, 0, 1], 1000).into();
+        let mut cache = ReplyCache::new(2);
+        cache.insert((1, addr, 100, 1), vec![1]);
+        cache.insert((2, addr, 100, 1), vec![2]);
+        cache.insert((1, addr, 100, 1), vec![3]);
+        assert_eq!(cache.get(&(1, addr, 100, 1)), Some(&[3][..]));
+        assert_eq!(cache.get(&(1, addr, 100, 2)), None);
+
+        cache.insert((3, addr, 100, 1), vec![4]);
+        assert_eq!(cache.get(&(1, addr, 100, 1)), None);
+        assert_eq!(cache.get(&(2, addr, 100, 1)), Some(&[2][..]));
+
+        cache.set_capacity(0);
+        assert_eq!(cache.get(&(3, addr, 100, 1)), None);
+    }
+}
@@ -0,0 +1,765 @@
+//! ONC RPC clients: `RpcClient` multiplexes calls over one stream connection,
+//! `UdpRpcClient` retransmits calls over UDP until they are answered.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::io::{self, BufWriter, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::{
+    AuthStat, CallBody, DEFAULT_MAX_RECORD_LEN, OpaqueAuth, RejectedReply, ReplyBody, ReplyData,
+    RpcMessage,
+};
+use crate::error::Error;
+use crate::record_marking::{RecordReader, RecordWriter};
+use crate::{from_bytes, from_bytes_prefix, to_bytes_into};
+
+/// time a call waits for its reply unless told otherwise, as in rpcgen clients
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// time `UdpRpcClient` waits before sending a call again
+pub const DEFAULT_RETRANSMIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// shortest retransmit interval `UdpRpcClient` takes, so that it never sends
+/// a call again without giving the reply a chance to come
+pub const MIN_RETRANSMIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// largest UDP reply `UdpRpcClient` can receive
+const MAX_DATAGRAM_LEN: usize = 65535;
+
+#[derive(Debug)]
+pub enum RpcError {
+    /// encoding the call or decoding the reply failed, or the transport did
+    Xdr(Error),
+    /// no reply came within the timeout
+    Timeout,
+    /// the connection closed before the reply came
+    Disconnected,
+    ProgUnavail,
+    /// lowest and highest version of the program the server supports
+    ProgMismatch {
+        low: u32,
+        high: u32,
+    },
+    ProcUnavail,
+    GarbageArgs,
+    SystemErr,
+    /// lowest and highest RPC version the server supports
+    RpcMismatch {
+        low: u32,
+        high: u32,
+    },
+    AuthError(AuthStat),
+}
+
+impl From<Error> for RpcError {
+    fn from(err: Error) -> Self {
+        RpcError::Xdr(err)
+    }
+}
+
+impl From<io::Error> for RpcError {
+    fn from(err: io::Error) -> Self {
+        RpcError::Xdr(Error::Io(err))
+    }
+}
+
+impl Display for RpcError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RpcError::Xdr(err) => write!(formatter, "{}", err),
+            RpcError::Timeout => formatter.write_str("timed out waiting for reply"),
+            RpcError::Disconnected => formatter.write_str("connection closed before reply"),
+            RpcError::ProgUnavail => formatter.write_str("program unavailable"),
+            RpcError::ProgMismatch { low, high } => {
+                write!(
+                    formatter,
+                    "program version mismatch, server supports {}..={}",
+                    low, high
+                )
+            }
+            RpcError::ProcUnavail => formatter.write_str("procedure unavailable"),
+            RpcError::GarbageArgs => formatter.write_str("server could not decode arguments"),
+            RpcError::SystemErr => formatter.write_str("system error on server"),
+            RpcError::RpcMismatch { low, high } => {
+                write!(
+                    formatter,
+                    "RPC version mismatch, server supports {}..={}",
+                    low, high
+                )
+            }
+            RpcError::AuthError(stat) => write!(formatter, "authentication error {:?}", stat),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RpcError::Xdr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+pub type RpcResult<T> = std::result::Result<T, RpcError>;
+
+/// first xid of a client, varying between runs so a restarted client is unlikely
+/// to reuse the xids of its previous run within a server's duplicate request cache
+fn initial_xid() -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            elapsed.subsec_nanos() ^ elapsed.as_secs() as u32
+        });
+    nanos ^ std::process::id().rotate_left(16)
+}
+
+/// what every call of a client shares
+#[derive(Debug)]
+struct CallTemplate {
+    prog: u32,
+    vers: u32,
+    cred: OpaqueAuth,
+    verf: OpaqueAuth,
+    timeout: Duration,
+    xid: AtomicU32,
+}
+
+impl CallTemplate {
+    fn new(prog: u32, vers: u32) -> Self {
+        CallTemplate {
+            prog,
+            vers,
+            cred: OpaqueAuth::none(),
+            verf: OpaqueAuth::none(),
+            timeout: DEFAULT_TIMEOUT,
+            xid: AtomicU32::new(initial_xid()),
+        }
+    }
+
+    /// encode a call record with a fresh xid
+    fn encode<A: ?Sized + Serialize>(&self, proc: u32, args: &A) -> RpcResult<(u32, Vec<u8>)> {
+        let xid = self.xid.fetch_add(1, Ordering::Relaxed);
+        let mut call = CallBody::new(self.prog, self.vers, proc);
+        call.cred = self.cred.clone();
+        call.verf = self.verf.clone();
+        let mut record = Vec::new();
+        to_bytes_into(&RpcMessage::call(xid, call), &mut record)?;
+        to_bytes_into(args, &mut record)?;
+        Ok((xid, record))
+    }
+}
+
+/// `mtype` of a reply
+const REPLY: u32 = 1;
+
+/// decode the results of a reply record, or the error it reports
+fn decode_reply<R: DeserializeOwned>(record: &[u8]) -> RpcResult<R> {
+    let ((_xid, _mtype, reply), results) = from_bytes_prefix::<(u32, u32, ReplyBody)>(record)?;
+    match reply {
+        ReplyBody::Accepted(accepted) => match accepted.reply_data {
+            ReplyData::Success => Ok(from_bytes(results)?),
+            ReplyData::ProgUnavail => Err(RpcError::ProgUnavail),
+            ReplyData::ProgMismatch { low, high } => Err(RpcError::ProgMismatch { low, high }),
+            ReplyData::ProcUnavail => Err(RpcError::ProcUnavail),
+            ReplyData::GarbageArgs => Err(RpcError::GarbageArgs),
+            ReplyData::SystemErr => Err(RpcError::SystemErr),
+        },
+        ReplyBody::Denied(RejectedReply::RpcMismatch { low, high }) => {
+            Err(RpcError::RpcMismatch { low, high })
+        }
+        ReplyBody::Denied(RejectedReply::AuthError(stat)) => Err(RpcError::AuthError(stat)),
+    }
+}
+
+/// xid of a reply record, `None` for anything else
+fn reply_xid(record: &[u8]) -> Option<u32> {
+    match from_bytes_prefix::<(u32, u32)>(record) {
+        Ok(((xid, REPLY), _)) => Some(xid),
+        _ => None,
+    }
+}
+
+/// waiters for replies, by xid; `None` once the connection is gone
+type Pending = Arc<Mutex<Option<HashMap<u32, mpsc::Sender<Vec<u8>>>>>>;
+
+/// Client of one version of one program over a stream connection.
+///
+/// Calls may be made from many threads at once, or started with `start_call`
+/// and waited for later, and are all multiplexed over the one connection;
+/// a background thread, started by the first call, reads the replies and
+/// hands each to its caller by xid.
+pub struct RpcClient {
+    template: CallTemplate,
+    writer: Mutex<RecordWriter<BufWriter<Box<dyn Write + Send>>>>,
+    /// reading half of the connection, until the reply thread takes it
+    reader: Mutex<Option<Box<dyn io::Read + Send>>>,
+    max_record_len: usize,
+    pending: Pending,
+    /// closes the connection on drop, so the reply thread stops reading
+    shutdown: Option<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl RpcClient {
+    /// call `prog` version `vers` over a new TCP connection to `addr`
+    pub fn connect<A: ToSocketAddrs>(addr: A, prog: u32, vers: u32) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let mut client = RpcClient::new(stream.try_clone()?, stream.try_clone()?, prog, vers);
+        client.shutdown = Some(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }));
+        Ok(client)
+    }
+
+    /// call `prog` version `vers` over a new Unix socket connection to `path`
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<std::path::Path>>(
+        path: P,
+        prog: u32,
+        vers: u32,
+    ) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        let mut client = RpcClient::new(stream.try_clone()?, stream.try_clone()?, prog, vers);
+        client.shutdown = Some(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }));
+        Ok(client)
+    }
+
+    /// call `prog` version `vers` over a connection split into its reading and
+    /// writing halves; once the client is dropped, its reply thread stops at
+    /// the next record or at the end of `reader`, whichever comes first
+    pub fn new<R, W>(reader: R, writer: W, prog: u32, vers: u32) -> Self
+    where
+        R: io::Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let reader: Box<dyn io::Read + Send> = Box::new(reader);
+        let writer: Box<dyn Write + Send> = Box::new(writer);
+        RpcClient {
+            template: CallTemplate::new(prog, vers),
+            writer: Mutex::new(RecordWriter::new(BufWriter::new(writer))),
+            reader: Mutex::new(Some(reader)),
+            max_record_len: DEFAULT_MAX_RECORD_LEN,
+            pending: Arc::new(Mutex::new(Some(HashMap::new()))),
+            shutdown: None,
+        }
+    }
+
+    /// credential and verifier sent with every call
+    pub fn with_auth(mut self, cred: OpaqueAuth, verf: OpaqueAuth) -> Self {
+        self.template.cred = cred;
+        self.template.verf = verf;
+        self
+    }
+
+    /// time each call waits for its reply
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.template.timeout = timeout;
+        self
+    }
+
+    /// drop the connection on replies longer than `len` bytes, failing the
+    /// calls still waiting with `RpcError::Disconnected`
+    pub fn with_max_record_len(mut self, len: usize) -> Self {
+        self.max_record_len = len;
+        self
+    }
+
+    /// start the thread reading replies, unless it runs already
+    fn start_reader(&self) {
+        let Some(reader) = self.reader.lock().unwrap().take() else {
+            return;
+        };
+        let replies = Arc::clone(&self.pending);
+        let max_record_len = self.max_record_len;
+        thread::spawn(move || {
+            let mut reader =
+                RecordReader::new(io::BufReader::new(reader)).with_max_record_len(max_record_len);
+            while let Ok(Some(record)) = reader.read_record() {
+                let Some(xid) = reply_xid(record) else {
+                    continue;
+                };
+                // gone once the client is dropped
+                let Some(waiter) = replies.lock().unwrap().as_mut().map(|map| map.remove(&xid))
+                else {
+                    break;
+                };
+                // replies to calls that timed out have no waiter left
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(record.to_vec());
+                }
+            }
+            replies.lock().unwrap().take();
+        });
+    }
+
+    /// call procedure `proc` and wait for its results
+    pub fn call<A, R>(&self, proc: u32, args: &A) -> RpcResult<R>
+    where
+        A: ?Sized + Serialize,
+        R: DeserializeOwned,
+    {
+        self.start_call(proc, args)?.wait()
+    }
+
+    /// send a call to procedure `proc` without waiting for its reply
+    pub fn start_call<A>(&self, proc: u32, args: &A) -> RpcResult<PendingCall<'_>>
+    where
+        A: ?Sized + Serialize,
+    {
+        let (xid, record) = self.template.encode(proc, args)?;
+        self.start_reader();
+        let (sender, receiver) = mpsc::channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(map) => map.insert(xid, sender),
+            None => return Err(RpcError::Disconnected),
+        };
+        let mut writer = self.writer.lock().unwrap();
+        let sent = writer
+            .write_record(&record)
+            .and_then(|()| Ok(writer.get_mut().flush()?));
+        drop(writer);
+        if let Err(err) = sent {
+            self.forget(xid);
+            return Err(err.into());
+        }
+        Ok(PendingCall {
+            client: self,
+            xid,
+            receiver,
+            timeout: self.template.timeout,
+        })
+    }
+
+    fn forget(&self, xid: u32) {
+        if let Some(map) = self.pending.lock().unwrap().as_mut() {
+            map.remove(&xid);
+        }
+    }
+}
+
+impl Drop for RpcClient {
+    /// stop the reply thread, right away for connections it can shut down
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().take();
+        if let Some(shutdown) = &self.shutdown {
+            shutdown();
+        }
+    }
+}
+
+/// Call sent by `RpcClient::start_call` whose reply has not been read yet.
+pub struct PendingCall<'a> {
+    client: &'a RpcClient,
+    xid: u32,
+    receiver: mpsc::Receiver<Vec<u8>>,
+    timeout: Duration,
+}
+
+impl PendingCall<'_> {
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    /// wait for the results, for at most the client's timeout from now
+    pub fn wait<R: DeserializeOwned>(self) -> RpcResult<R> {
+        let timeout = self.timeout;
+        self.wait_timeout(timeout)
+    }
+
+    pub fn wait_timeout<R: DeserializeOwned>(self, timeout: Duration) -> RpcResult<R> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(record) => decode_reply(&record),
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(RpcError::Timeout),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(RpcError::Disconnected),
+        }
+    }
+}
+
+impl Drop for PendingCall<'_> {
+    /// stop waiting for the reply, whether or not it came
+    fn drop(&mut self) {
+        self.client.forget(self.xid);
+    }
+}
+
+/// Client of one version of one program over UDP.
+///
+/// A call is sent again every `retransmit_interval` with the same xid until
+/// its reply comes or the timeout expires; replies to other xids are dropped.
+#[derive(Debug)]
+pub struct UdpRpcClient {
+    template: CallTemplate,
+    socket: UdpSocket,
+    retransmit_interval: Duration,
+}
+
+impl UdpRpcClient {
+    /// call `prog` version `vers` at `addr` from an ephemeral local port
+    pub fn connect<A: ToSocketAddrs>(addr: A, prog: u32, vers: u32) -> io::Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to call"))?;
+        let local: std::net::SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(UdpRpcClient {
+            template: CallTemplate::new(prog, vers),
+            socket,
+            retransmit_interval: DEFAULT_RETRANSMIT_INTERVAL,
+        })
+    }
+
+    pub fn with_auth(mut self, cred: OpaqueAuth, verf: OpaqueAuth) -> Self {
+        self.template.cred = cred;
+        self.template.verf = verf;
+        self
+    }
+
+    /// time each call waits for its reply, over all retransmissions
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.template.timeout = timeout;
+        self
+    }
+
+    /// time to wait for a reply before sending the call again, raised to
+    /// `MIN_RETRANSMIT_INTERVAL` if shorter
+    pub fn with_retransmit_interval(mut self, interval: Duration) -> Self {
+        self.retransmit_interval = interval.max(MIN_RETRANSMIT_INTERVAL);
+        self
+    }
+
+    /// call procedure `proc` and wait for its results, for at most the
+    /// client's timeout
+    pub fn call<A, R>(&mut self, proc: u32, args: &A) -> RpcResult<R>
+    where
+        A: ?Sized + Serialize,
+        R: DeserializeOwned,
+    {
+        let timeout = self.template.timeout;
+        self.call_timeout(proc, args, timeout)
+    }
+
+    /// call procedure `proc` and wait for its results, for at most `timeout`
+    /// over all retransmissions
+    pub fn call_timeout<A, R>(&mut self, proc: u32, args: &A, timeout: Duration) -> RpcResult<R>
+    where
+        A: ?Sized + Serialize,
+        R: DeserializeOwned,
+    {
+        let (xid, datagram) = self.template.encode(proc, args)?;
+        // `None` for timeouts too long to tell from waiting forever
+        let deadline = Instant::now().checked_add(timeout);
+        let mut buf = vec![0; MAX_DATAGRAM_LEN];
+        loop {
+            self.socket.send(&datagram)?;
+            let resend_at = [
+                Instant::now().checked_add(self.retransmit_interval),
+                deadline,
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            loop {
+                let now = Instant::now();
+                if deadline.is_some_and(|deadline| now >= deadline) {
+                    return Err(RpcError::Timeout);
+                }
+                if resend_at.is_some_and(|resend_at| now >= resend_at) {
+                    break;
+                }
+                self.socket
+                    .set_read_timeout(resend_at.map(|resend_at| resend_at - now))?;
+                match self.socket.recv(&mut buf) {
+                    Ok(len) if reply_xid(&buf[..len]) == Some(xid) => {
+                        return decode_reply(&buf[..len]);
+                    }
+                    Ok(_) => continue,
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::WouldBlock
+                                | io::ErrorKind::TimedOut
+                                | io::ErrorKind::Interrupted
+                                // an ICMP port unreachable from an earlier send
+                                | io::ErrorKind::ConnectionRefused
+                        ) =>
+                    {
+                        continue;
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::rpc::{AuthSysParams, ProcError, RpcServer};
+
+    const PROG: u32 = 0x2000_0002;
+
+    fn server() -> RpcServer {
+        let mut server = RpcServer::new();
+        server
+            .register(PROG, 1, 1, |_, (a, b): (u32, u32)| Ok(a + b))
+            .register(PROG, 1, 2, |call, ()| match call.auth_sys() {
+                Ok(params) => Ok(params.machinename),
+                Err(_) => Err(ProcError::Auth(AuthStat::AuthTooweak)),
+            })
+            .register(PROG, 1, 3, |_, millis: u64| {
+                thread::sleep(Duration::from_millis(millis));
+                Ok(())
+            })
+            .register(PROG, 1, 4, |_, len: u32| Ok(vec![0u32; len as usize]));
+        server
+    }
+
+    fn tcp_server() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = server();
+        thread::spawn(move || server.serve_tcp(&listener));
+        addr
+    }
+
+    #[test]
+    fn test_client_tcp() {
+        let client = RpcClient::connect(tcp_server(), PROG, 1).unwrap();
+        assert_eq!(client.call::<_, u32>(1, &(2u32, 3u32)).unwrap(), 5);
+        client.call::<_, ()>(0, &()).unwrap();
+
+        // pipelined on one connection, waited for out of order
+        let calls = (0..8u32)
+            .map(|i| client.start_call(1, &(i, 100u32)).unwrap())
+            .collect::<Vec<_>>();
+        for (i, call) in calls.into_iter().enumerate().rev() {
+            assert_eq!(call.wait::<u32>().unwrap(), i as u32 + 100);
+        }
+
+        thread::scope(|scope| {
+            for i in 0..4u32 {
+                let client = &client;
+                scope.spawn(move || {
+                    assert_eq!(client.call::<_, u32>(1, &(i, i)).unwrap(), 2 * i);
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_client_errors() {
+        let addr = tcp_server();
+        let client = RpcClient::connect(addr, PROG, 1).unwrap();
+        assert!(matches!(
+            client.call::<_, ()>(9, &()),
+            Err(RpcError::ProcUnavail)
+        ));
+        assert!(matches!(
+            client.call::<_, u32>(1, &1u32),
+            Err(RpcError::GarbageArgs)
+        ));
+        assert!(matches!(
+            client.call::<_, String>(2, &()),
+            Err(RpcError::AuthError(AuthStat::AuthTooweak))
+        ));
+
+        let params = AuthSysParams {
+            machinename: "client".to_string(),
+            ..Default::default()
+        };
+        let client = RpcClient::connect(addr, PROG, 1)
+            .unwrap()
+            .with_auth(OpaqueAuth::try_from(&params).unwrap(), OpaqueAuth::none());
+        assert_eq!(client.call::<_, String>(2, &()).unwrap(), "client");
+
+        let client = RpcClient::connect(addr, PROG, 4).unwrap();
+        assert!(matches!(
+            client.call::<_, ()>(0, &()),
+            Err(RpcError::ProgMismatch { low: 1, high: 1 })
+        ));
+        let client = RpcClient::connect(addr, PROG + 1, 1).unwrap();
+        assert!(matches!(
+            client.call::<_, ()>(0, &()),
+            Err(RpcError::ProgUnavail)
+        ));
+    }
+
+    #[test]
+    fn test_client_timeout() {
+        let client = RpcClient::connect(tcp_server(), PROG, 1)
+            .unwrap()
+            .with_timeout(Duration::from_millis(50));
+        assert!(matches!(
+            client.call::<_, ()>(3, &500u64),
+            Err(RpcError::Timeout)
+        ));
+        // the late reply is dropped, later calls still get theirs
+        let call = client.start_call(1, &(1u32, 1u32)).unwrap();
+        assert_eq!(call.wait_timeout::<u32>(Duration::from_secs(5)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_client_dropped_pending_call() {
+        let client = RpcClient::connect(tcp_server(), PROG, 1).unwrap();
+        for _ in 0..4 {
+            drop(client.start_call(3, &50u64).unwrap());
+        }
+        let pending =
+            |client: &RpcClient| client.pending.lock().unwrap().as_ref().map(HashMap::len);
+        assert_eq!(pending(&client), Some(0));
+        assert_eq!(client.call::<_, u32>(1, &(1u32, 2u32)).unwrap(), 3);
+        assert_eq!(pending(&client), Some(0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_unix() {
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("xdr_brk_client_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = server();
+        thread::spawn(move || server.serve_unix(&listener));
+        let client = RpcClient::connect_unix(&path, PROG, 1).unwrap();
+        assert_eq!(client.call::<_, u32>(1, &(2u32, 3u32)).unwrap(), 5);
+        drop(client);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_client_max_record_len() {
+        let addr = tcp_server();
+        let client = RpcClient::connect(addr, PROG, 1).unwrap();
+        assert_eq!(client.call::<_, Vec<u32>>(4, &64u32).unwrap().len(), 64);
+
+        // the reply header and 4 + 64 * 4 bytes of results
+        let client = RpcClient::connect(addr, PROG, 1)
+            .unwrap()
+            .with_max_record_len(256);
+        assert_eq!(client.call::<_, Vec<u32>>(4, &4u32).unwrap().len(), 4);
+        assert!(matches!(
+            client.call::<_, Vec<u32>>(4, &64u32),
+            Err(RpcError::Disconnected)
+        ));
+    }
+
+    #[test]
+    fn test_client_disconnected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || drop(listener.accept()));
+        let client = RpcClient::connect(addr, PROG, 1).unwrap();
+        let result = client.call::<_, ()>(0, &());
+        assert!(matches!(
+            result,
+            Err(RpcError::Disconnected | RpcError::Xdr(Error::Io(_)))
+        ));
+    }
+
+    #[test]
+    fn test_client_udp_retransmit() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let server = server();
+        thread::spawn(move || {
+            let mut buf = vec![0; MAX_DATAGRAM_LEN];
+            let mut received = 0;
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).unwrap();
+                received += 1;
+                // lose the first datagram, so the call has to be sent again
+                if received == 1 {
+                    continue;
+                }
+                if let Some(reply) = server.handle_record(&buf[..len]) {
+                    socket.send_to(&reply, peer).unwrap();
+                }
+            }
+        });
+
+        let mut client = UdpRpcClient::connect(addr, PROG, 1)
+            .unwrap()
+            .with_retransmit_interval(Duration::from_millis(20))
+            .with_timeout(Duration::from_secs(5));
+        assert_eq!(client.call::<_, u32>(1, &(20u32, 22u32)).unwrap(), 42);
+        assert!(matches!(
+            client.call::<_, ()>(9, &()),
+            Err(RpcError::ProcUnavail)
+        ));
+    }
+
+    #[test]
+    fn test_client_udp_timeout() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut client = UdpRpcClient::connect(silent.local_addr().unwrap(), PROG, 1)
+            .unwrap()
+            .with_retransmit_interval(Duration::from_millis(10))
+            .with_timeout(Duration::from_millis(50));
+        assert!(matches!(
+            client.call::<_, ()>(0, &()),
+            Err(RpcError::Timeout)
+        ));
+
+        // a per-call timeout overrides the client's
+        let mut client = UdpRpcClient::connect(silent.local_addr().unwrap(), PROG, 1)
+            .unwrap()
+            .with_retransmit_interval(Duration::from_millis(10));
+        let start = Instant::now();
+        assert!(matches!(
+            client.call_timeout::<_, ()>(0, &(), Duration::from_millis(50)),
+            Err(RpcError::Timeout)
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_client_udp_retransmit_interval_bounds() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let server = server();
+        thread::spawn(move || server.serve_udp(&socket));
+
+        // a zero interval is raised, so the reply still has a chance to come
+        let mut client = UdpRpcClient::connect(addr, PROG, 1)
+            .unwrap()
+            .with_retransmit_interval(Duration::ZERO);
+        assert_eq!(client.retransmit_interval, MIN_RETRANSMIT_INTERVAL);
+        assert_eq!(client.call::<_, u32>(1, &(1u32, 2u32)).unwrap(), 3);
+
+        // too long to add to now, meaning no deadline at all
+        let mut client = UdpRpcClient::connect(addr, PROG, 1)
+            .unwrap()
+            .with_retransmit_interval(Duration::MAX)
+            .with_timeout(Duration::MAX);
+        assert_eq!(client.call::<_, u32>(1, &(2u32, 3u32)).unwrap(), 5);
+        assert_eq!(
+            client
+                .call_timeout::<_, u32>(1, &(3u32, 4u32), Duration::MAX)
+                .unwrap(),
+            7
+        );
+    }
+}
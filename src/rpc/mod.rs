@@ -0,0 +1,375 @@
+//! ONC RPC version 2 message types (RFC 5531).
+//!
+//! Only the message header is modeled: procedure arguments follow a
+//! `CallBody`, and results follow an `AcceptedReply` whose `reply_data` is
+//! `ReplyData::Success`, in the same record. Encode them one after the other,
+//! e.g. with `to_bytes_into`, and split them again with `from_bytes_prefix`.
+
+use serde::{Deserialize, Serialize};
+
+mod auth;
+mod cache;
+mod client;
+pub mod portmap;
+mod server;
+
+pub use auth::{AuthNone, AuthSysParams, MAX_GIDS, MAX_MACHINE_NAME_LEN};
+pub use client::{
+    DEFAULT_RETRANSMIT_INTERVAL, DEFAULT_TIMEOUT, MIN_RETRANSMIT_INTERVAL, PendingCall, RpcClient,
+    RpcError, RpcResult, UdpRpcClient,
+};
+pub use server::{DEFAULT_MAX_DATAGRAM_LEN, DEFAULT_REPLY_CACHE_SIZE, ProcError, RpcServer};
+
+pub use crate::record_marking::DEFAULT_MAX_RECORD_LEN;
+
+use crate::{VariableLengthBytes, XDREnumDeserialize, XDREnumSerialize};
+
+/// `rpcvers` of every call this module builds
+pub const RPC_VERSION: u32 = 2;
+
+/// maximum length of the body of an `OpaqueAuth`
+pub const MAX_AUTH_BYTES: usize = 400;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum AuthFlavor {
+    AuthNone = 0,
+    AuthSys = 1,
+    AuthShort = 2,
+    AuthDh = 3,
+    RpcsecGss = 6,
+    /// flavor not listed above, kept so credentials can be passed through
+    #[default_arm]
+    Unknown(u32),
+}
+
+impl From<AuthFlavor> for u32 {
+    fn from(flavor: AuthFlavor) -> u32 {
+        match flavor {
+            AuthFlavor::AuthNone => 0,
+            AuthFlavor::AuthSys => 1,
+            AuthFlavor::AuthShort => 2,
+            AuthFlavor::AuthDh => 3,
+            AuthFlavor::RpcsecGss => 6,
+            AuthFlavor::Unknown(flavor) => flavor,
+        }
+    }
+}
+
+/// Credential or verifier, opaque to this layer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpaqueAuth {
+    pub flavor: AuthFlavor,
+    pub body: VariableLengthBytes<MAX_AUTH_BYTES>,
+}
+
+impl OpaqueAuth {
+    /// `AUTH_NONE` with an empty body
+    pub fn none() -> Self {
+        OpaqueAuth {
+            flavor: AuthFlavor::AuthNone,
+            body: VariableLengthBytes::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcMessage {
+    pub xid: u32,
+    pub body: MessageBody,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum MessageBody {
+    Call(CallBody) = 0,
+    Reply(ReplyBody) = 1,
+}
+
+/// Header of a call, followed by the procedure arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallBody {
+    pub rpcvers: u32,
+    pub prog: u32,
+    pub vers: u32,
+    pub proc: u32,
+    pub cred: OpaqueAuth,
+    pub verf: OpaqueAuth,
+}
+
+impl CallBody {
+    /// call of `RPC_VERSION` with `AUTH_NONE` credential and verifier
+    pub fn new(prog: u32, vers: u32, proc: u32) -> Self {
+        CallBody {
+            rpcvers: RPC_VERSION,
+            prog,
+            vers,
+            proc,
+            cred: OpaqueAuth::none(),
+            verf: OpaqueAuth::none(),
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum ReplyBody {
+    Accepted(AcceptedReply) = 0,
+    Denied(RejectedReply) = 1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptedReply {
+    pub verf: OpaqueAuth,
+    pub reply_data: ReplyData,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum AcceptStat {
+    Success = 0,
+    ProgUnavail = 1,
+    ProgMismatch = 2,
+    ProcUnavail = 3,
+    GarbageArgs = 4,
+    SystemErr = 5,
+}
+
+/// Union switched on `AcceptStat` inside an accepted reply.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum ReplyData {
+    /// the procedure results follow the reply header
+    Success = 0,
+    ProgUnavail = 1,
+    /// lowest and highest version of the program supported
+    ProgMismatch {
+        low: u32,
+        high: u32,
+    } = 2,
+    ProcUnavail = 3,
+    GarbageArgs = 4,
+    SystemErr = 5,
+}
+
+impl ReplyData {
+    pub fn stat(&self) -> AcceptStat {
+        match self {
+            ReplyData::Success => AcceptStat::Success,
+            ReplyData::ProgUnavail => AcceptStat::ProgUnavail,
+            ReplyData::ProgMismatch { .. } => AcceptStat::ProgMismatch,
+            ReplyData::ProcUnavail => AcceptStat::ProcUnavail,
+            ReplyData::GarbageArgs => AcceptStat::GarbageArgs,
+            ReplyData::SystemErr => AcceptStat::SystemErr,
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum RejectStat {
+    RpcMismatch = 0,
+    AuthError = 1,
+}
+
+/// Union switched on `RejectStat`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum RejectedReply {
+    /// lowest and highest RPC version supported
+    RpcMismatch {
+        low: u32,
+        high: u32,
+    } = 0,
+    AuthError(AuthStat) = 1,
+}
+
+impl RejectedReply {
+    pub fn stat(&self) -> RejectStat {
+        match self {
+            RejectedReply::RpcMismatch { .. } => RejectStat::RpcMismatch,
+            RejectedReply::AuthError(_) => RejectStat::AuthError,
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, XDREnumSerialize, XDREnumDeserialize)]
+pub enum AuthStat {
+    AuthOk = 0,
+    AuthBadcred = 1,
+    AuthRejectedcred = 2,
+    AuthBadverf = 3,
+    AuthRejectedverf = 4,
+    AuthTooweak = 5,
+    AuthInvalidresp = 6,
+    AuthFailed = 7,
+    AuthKerbGeneric = 8,
+    AuthTimeexpire = 9,
+    AuthTktFile = 10,
+    AuthDecode = 11,
+    AuthNetAddr = 12,
+    RpcsecGssCredproblem = 13,
+    RpcsecGssCtxproblem = 14,
+    /// status not listed in RFC 5531, kept so replies can still be decoded
+    #[default_arm]
+    Unknown(u32),
+}
+
+impl RpcMessage {
+    pub fn call(xid: u32, call: CallBody) -> Self {
+        RpcMessage {
+            xid,
+            body: MessageBody::Call(call),
+        }
+    }
+
+    /// accepted reply with an `AUTH_NONE` verifier
+    pub fn accepted(xid: u32, reply_data: ReplyData) -> Self {
+        RpcMessage {
+            xid,
+            body: MessageBody::Reply(ReplyBody::Accepted(AcceptedReply {
+                verf: OpaqueAuth::none(),
+                reply_data,
+            })),
+        }
+    }
+
+    pub fn denied(xid: u32, rejected: RejectedReply) -> Self {
+        RpcMessage {
+            xid,
+            body: MessageBody::Reply(ReplyBody::Denied(rejected)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_bytes, from_bytes_prefix, to_bytes, to_bytes_into};
+
+    fn round_trip(message: &RpcMessage, wire: &[u8]) {
+        assert_eq!(to_bytes(message).unwrap(), wire);
+        assert_eq!(&from_bytes::<RpcMessage>(wire).unwrap(), message);
+    }
+
+    #[test]
+    fn test_rpc_call() {
+        // NULL procedure of the portmapper, version 2 (RFC 1833)
+        let message = RpcMessage::call(0x1234_5678, CallBody::new(100000, 2, 0));
+        let wire = [
+            0x12, 0x34, 0x56, 0x78, // xid
+            0, 0, 0, 0, // mtype CALL
+            0, 0, 0, 2, // rpcvers
+            0, 1, 0x86, 0xa0, // prog 100000
+            0, 0, 0, 2, // vers
+            0, 0, 0, 0, // proc
+            0, 0, 0, 0, 0, 0, 0, 0, // cred AUTH_NONE, empty body
+            0, 0, 0, 0, 0, 0, 0, 0, // verf AUTH_NONE, empty body
+        ];
+        round_trip(&message, &wire);
+    }
+
+    #[test]
+    fn test_rpc_call_with_arguments() {
+        let mut call = CallBody::new(100003, 3, 1);
+        call.cred = OpaqueAuth {
+            flavor: AuthFlavor::Unknown(390003),
+            body: VariableLengthBytes::new(vec![0xde, 0xad, 0xbe]).unwrap(),
+        };
+        let mut wire = Vec::new();
+        to_bytes_into(&RpcMessage::call(7, call.clone()), &mut wire).unwrap();
+        to_bytes_into(&42u32, &mut wire).unwrap();
+        assert_eq!(
+            wire[24..36],
+            [
+                0, 5, 0xf3, 0x73, // cred flavor 390003
+                0, 0, 0, 3, // body length
+                0xde, 0xad, 0xbe, 0, // body with padding
+            ]
+        );
+
+        let (message, args) = from_bytes_prefix::<RpcMessage>(&wire).unwrap();
+        assert_eq!(message, RpcMessage::call(7, call));
+        assert_eq!(from_bytes::<u32>(args).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_rpc_accepted_reply() {
+        let message = RpcMessage::accepted(1, ReplyData::Success);
+        let wire = [
+            0, 0, 0, 1, // xid
+            0, 0, 0, 1, // mtype REPLY
+            0, 0, 0, 0, // MSG_ACCEPTED
+            0, 0, 0, 0, 0, 0, 0, 0, // verf AUTH_NONE
+            0, 0, 0, 0, // SUCCESS
+        ];
+        round_trip(&message, &wire);
+
+        let message = RpcMessage::accepted(2, ReplyData::ProgMismatch { low: 2, high: 4 });
+        let wire = [
+            0, 0, 0, 2, // xid
+            0, 0, 0, 1, // mtype REPLY
+            0, 0, 0, 0, // MSG_ACCEPTED
+            0, 0, 0, 0, 0, 0, 0, 0, // verf AUTH_NONE
+            0, 0, 0, 2, // PROG_MISMATCH
+            0, 0, 0, 2, // low
+            0, 0, 0, 4, // high
+        ];
+        round_trip(&message, &wire);
+        assert_eq!(
+            ReplyData::ProgMismatch { low: 2, high: 4 }.stat(),
+            AcceptStat::ProgMismatch
+        );
+    }
+
+    #[test]
+    fn test_rpc_rejected_reply() {
+        let message = RpcMessage::denied(3, RejectedReply::RpcMismatch { low: 2, high: 2 });
+        let wire = [
+            0, 0, 0, 3, // xid
+            0, 0, 0, 1, // mtype REPLY
+            0, 0, 0, 1, // MSG_DENIED
+            0, 0, 0, 0, // RPC_MISMATCH
+            0, 0, 0, 2, // low
+            0, 0, 0, 2, // high
+        ];
+        round_trip(&message, &wire);
+
+        let rejected = RejectedReply::AuthError(AuthStat::AuthTooweak);
+        let wire = [
+            0, 0, 0, 4, // xid
+            0, 0, 0, 1, // mtype REPLY
+            0, 0, 0, 1, // MSG_DENIED
+            0, 0, 0, 1, // AUTH_ERROR
+            0, 0, 0, 5, // AUTH_TOOWEAK
+        ];
+        round_trip(&RpcMessage::denied(4, rejected), &wire);
+        assert_eq!(rejected.stat(), RejectStat::AuthError);
+
+        let rejected = RejectedReply::AuthError(AuthStat::Unknown(15));
+        let wire = [
+            0, 0, 0, 4, // xid
+            0, 0, 0, 1, // mtype REPLY
+            0, 0, 0, 1, // MSG_DENIED
+            0, 0, 0, 1, // AUTH_ERROR
+            0, 0, 0, 15, // not in RFC 5531
+        ];
+        round_trip(&RpcMessage::denied(4, rejected), &wire);
+    }
+
+    #[test]
+    fn test_rpc_invalid_message() {
+        let wire = [
+            0, 0, 0, 1, // xid
+            0, 0, 0, 2, // no such mtype
+        ];
+        assert!(from_bytes::<RpcMessage>(&wire).is_err());
+
+        let mut wire = to_bytes(&RpcMessage::call(1, CallBody::new(1, 1, 1))).unwrap();
+        // cred body length over MAX_AUTH_BYTES
+        wire[28..32].copy_from_slice(&401u32.to_be_bytes());
+        wire.resize(wire.len() + 404, 0);
+        assert!(from_bytes::<RpcMessage>(&wire).is_err());
+    }
+}
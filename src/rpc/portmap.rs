@@ -0,0 +1,509 @@
+//! Portmapper version 2 and rpcbind versions 3 and 4 (RFC 1833), with an
+//! in-process stand-in rpcbind and clients to register and look up programs.
+
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{RpcClient, RpcError, RpcResult, RpcServer};
+use crate::{Error, OptionalList, UniversalAddr, VariableLengthBytes};
+
+/// well-known port of the portmapper and rpcbind
+pub const PMAP_PORT: u16 = 111;
+pub const PMAP_PROG: u32 = 100000;
+pub const PMAP_VERS: u32 = 2;
+pub const RPCB_VERS: u32 = 3;
+pub const RPCB_VERS4: u32 = 4;
+
+/// `Mapping::prot` of TCP
+pub const IPPROTO_TCP: u32 = 6;
+/// `Mapping::prot` of UDP
+pub const IPPROTO_UDP: u32 = 17;
+
+pub const PMAPPROC_NULL: u32 = 0;
+pub const PMAPPROC_SET: u32 = 1;
+pub const PMAPPROC_UNSET: u32 = 2;
+pub const PMAPPROC_GETPORT: u32 = 3;
+pub const PMAPPROC_DUMP: u32 = 4;
+pub const PMAPPROC_CALLIT: u32 = 5;
+
+pub const RPCBPROC_NULL: u32 = 0;
+pub const RPCBPROC_SET: u32 = 1;
+pub const RPCBPROC_UNSET: u32 = 2;
+pub const RPCBPROC_GETADDR: u32 = 3;
+pub const RPCBPROC_DUMP: u32 = 4;
+/// `RPCBPROC_BCAST` in version 4
+pub const RPCBPROC_CALLIT: u32 = 5;
+pub const RPCBPROC_GETTIME: u32 = 6;
+pub const RPCBPROC_UADDR2TADDR: u32 = 7;
+pub const RPCBPROC_TADDR2UADDR: u32 = 8;
+pub const RPCBPROC_GETVERSADDR: u32 = 9;
+pub const RPCBPROC_INDIRECT: u32 = 10;
+pub const RPCBPROC_GETADDRLIST: u32 = 11;
+pub const RPCBPROC_GETSTAT: u32 = 12;
+
+/// Portmapper `mapping`: where version `vers` of program `prog` listens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mapping {
+    pub prog: u32,
+    pub vers: u32,
+    /// `IPPROTO_TCP` or `IPPROTO_UDP`
+    pub prot: u32,
+    pub port: u32,
+}
+
+/// `pmaplist`, the result of `PMAPPROC_DUMP`
+pub type PmapList = OptionalList<Mapping>;
+
+/// Portmapper `call_args` of `PMAPPROC_CALLIT`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallArgs {
+    pub prog: u32,
+    pub vers: u32,
+    pub proc: u32,
+    pub args: VariableLengthBytes,
+}
+
+/// Portmapper `call_result` of `PMAPPROC_CALLIT`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallResult {
+    pub port: u32,
+    pub res: VariableLengthBytes,
+}
+
+/// rpcbind `rpcb`: where version `r_vers` of program `r_prog` listens.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rpcb {
+    pub r_prog: u32,
+    pub r_vers: u32,
+    /// network id, such as `tcp`, `udp`, `tcp6` or `udp6`
+    pub r_netid: String,
+    /// universal address, such as `127.0.0.1.8.1` for port 2049, see
+    /// `UniversalAddr`; empty in queries
+    pub r_addr: String,
+    pub r_owner: String,
+}
+
+/// `rp__list`, the result of `RPCBPROC_DUMP`
+pub type RpcbList = OptionalList<Rpcb>;
+
+/// rpcbind `rpcb_rmtcallargs` of `RPCBPROC_CALLIT`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RmtCallArgs {
+    pub prog: u32,
+    pub vers: u32,
+    pub proc: u32,
+    pub args: VariableLengthBytes,
+}
+
+/// rpcbind `rpcb_rmtcallres` of `RPCBPROC_CALLIT`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RmtCallRes {
+    pub addr: UniversalAddr,
+    pub results: VariableLengthBytes,
+}
+
+fn netid(prot: u32) -> Option<&'static str> {
+    match prot {
+        IPPROTO_TCP => Some("tcp"),
+        IPPROTO_UDP => Some("udp"),
+        _ => None,
+    }
+}
+
+/// Table of registered programs, answering portmapper and rpcbind calls.
+///
+/// It stands in for the system rpcbind in tests: `spawn_local` serves it on
+/// an ephemeral loopback port. Registrations are not checked against their
+/// owner, and `CALLIT` is not supported.
+#[derive(Debug, Clone, Default)]
+pub struct Rpcbind {
+    table: Arc<Mutex<Vec<Rpcb>>>,
+}
+
+impl Rpcbind {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// current registrations
+    pub fn registrations(&self) -> Vec<Rpcb> {
+        self.table.lock().unwrap().clone()
+    }
+
+    /// add an entry unless one for the same program, version and network id exists
+    pub fn set(&self, rpcb: Rpcb) -> bool {
+        let mut table = self.table.lock().unwrap();
+        let taken = table.iter().any(|entry| {
+            (entry.r_prog, entry.r_vers, &entry.r_netid)
+                == (rpcb.r_prog, rpcb.r_vers, &rpcb.r_netid)
+        });
+        if !taken {
+            table.push(rpcb);
+        }
+        !taken
+    }
+
+    /// remove the entries of the program and version, only those of the
+    /// network id unless it is empty
+    pub fn unset(&self, rpcb: &Rpcb) -> bool {
+        let mut table = self.table.lock().unwrap();
+        let before = table.len();
+        table.retain(|entry| {
+            (entry.r_prog, entry.r_vers) != (rpcb.r_prog, rpcb.r_vers)
+                || (!rpcb.r_netid.is_empty() && entry.r_netid != rpcb.r_netid)
+        });
+        table.len() < before
+    }
+
+    /// universal address of the program and version on the network id, if registered
+    pub fn getaddr(&self, prog: u32, vers: u32, netid: &str) -> Option<String> {
+        let table = self.table.lock().unwrap();
+        table
+            .iter()
+            .find(|entry| {
+                (entry.r_prog, entry.r_vers, entry.r_netid.as_str()) == (prog, vers, netid)
+            })
+            .map(|entry| entry.r_addr.clone())
+    }
+
+    fn mapping_to_rpcb(mapping: &Mapping) -> Option<Rpcb> {
+        let port = u16::try_from(mapping.port).ok()?;
+        Some(Rpcb {
+            r_prog: mapping.prog,
+            r_vers: mapping.vers,
+            r_netid: netid(mapping.prot)?.to_string(),
+            r_addr: UniversalAddr::new([0, 0, 0, 0].into(), port).to_string(),
+            r_owner: String::new(),
+        })
+    }
+
+    /// register the portmapper and rpcbind procedures on `server`
+    pub fn register_procedures(&self, server: &mut RpcServer) {
+        let rpcbind = self.clone();
+        server.register_non_idempotent(PMAP_PROG, PMAP_VERS, PMAPPROC_SET, move |_, m: Mapping| {
+            Ok(Self::mapping_to_rpcb(&m).is_some_and(|rpcb| rpcbind.set(rpcb)))
+        });
+        let rpcbind = self.clone();
+        server.register_non_idempotent(
+            PMAP_PROG,
+            PMAP_VERS,
+            PMAPPROC_UNSET,
+            move |_, m: Mapping| {
+                let rpcb = Rpcb {
+                    r_prog: m.prog,
+                    r_vers: m.vers,
+                    ..Default::default()
+                };
+                Ok(rpcbind.unset(&rpcb))
+            },
+        );
+        let rpcbind = self.clone();
+        server.register(
+            PMAP_PROG,
+            PMAP_VERS,
+            PMAPPROC_GETPORT,
+            move |_, m: Mapping| {
+                let port = netid(m.prot)
+                    .and_then(|netid| rpcbind.getaddr(m.prog, m.vers, netid))
+                    .and_then(|uaddr| uaddr.parse::<UniversalAddr>().ok())
+                    .map_or(0, |uaddr| uaddr.0.port());
+                Ok(u32::from(port))
+            },
+        );
+        let rpcbind = self.clone();
+        server.register(PMAP_PROG, PMAP_VERS, PMAPPROC_DUMP, move |_, ()| {
+            let list: PmapList = rpcbind
+                .registrations()
+                .iter()
+                .filter_map(|entry| {
+                    let prot = match entry.r_netid.as_str() {
+                        "tcp" => IPPROTO_TCP,
+                        "udp" => IPPROTO_UDP,
+                        _ => return None,
+                    };
+                    let port = entry.r_addr.parse::<UniversalAddr>().ok()?.0.port();
+                    Some(Mapping {
+                        prog: entry.r_prog,
+                        vers: entry.r_vers,
+                        prot,
+                        port: u32::from(port),
+                    })
+                })
+                .collect();
+            Ok(list)
+        });
+
+        for vers in [RPCB_VERS, RPCB_VERS4] {
+            let rpcbind = self.clone();
+            server.register_non_idempotent(PMAP_PROG, vers, RPCBPROC_SET, move |_, rpcb: Rpcb| {
+                Ok(rpcbind.set(rpcb))
+            });
+            let rpcbind = self.clone();
+            server.register_non_idempotent(
+                PMAP_PROG,
+                vers,
+                RPCBPROC_UNSET,
+                move |_, rpcb: Rpcb| Ok(rpcbind.unset(&rpcb)),
+            );
+            let rpcbind = self.clone();
+            server.register(PMAP_PROG, vers, RPCBPROC_GETADDR, move |_, rpcb: Rpcb| {
+                Ok(rpcbind
+                    .getaddr(rpcb.r_prog, rpcb.r_vers, &rpcb.r_netid)
+                    .unwrap_or_default())
+            });
+            let rpcbind = self.clone();
+            server.register(PMAP_PROG, vers, RPCBPROC_DUMP, move |_, ()| {
+                Ok(RpcbList::from(rpcbind.registrations()))
+            });
+            server.register(PMAP_PROG, vers, RPCBPROC_GETTIME, |_, ()| {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH);
+                Ok(now.map_or(0, |now| now.as_secs() as u32))
+            });
+        }
+    }
+
+    /// serve this table over TCP on an ephemeral port of 127.0.0.1, from a
+    /// background thread, returning the address to reach it at
+    pub fn spawn_local(&self) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let mut server = RpcServer::new();
+        self.register_procedures(&mut server);
+        thread::spawn(move || server.serve_tcp(&listener));
+        Ok(addr)
+    }
+}
+
+/// Client of rpcbind version 3.
+pub struct RpcbindClient {
+    client: RpcClient,
+}
+
+impl RpcbindClient {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(RpcbindClient {
+            client: RpcClient::connect(addr, PMAP_PROG, RPCB_VERS)?,
+        })
+    }
+
+    /// the rpcbind of this host, on `PMAP_PORT`
+    pub fn local() -> io::Result<Self> {
+        Self::connect(("127.0.0.1", PMAP_PORT))
+    }
+
+    pub fn set(&self, rpcb: &Rpcb) -> RpcResult<bool> {
+        self.client.call(RPCBPROC_SET, rpcb)
+    }
+
+    pub fn unset(&self, rpcb: &Rpcb) -> RpcResult<bool> {
+        self.client.call(RPCBPROC_UNSET, rpcb)
+    }
+
+    /// universal address of the program, `None` if it is not registered
+    pub fn getaddr(&self, prog: u32, vers: u32, netid: &str) -> RpcResult<Option<String>> {
+        let rpcb = Rpcb {
+            r_prog: prog,
+            r_vers: vers,
+            r_netid: netid.to_string(),
+            ..Default::default()
+        };
+        let uaddr: String = self.client.call(RPCBPROC_GETADDR, &rpcb)?;
+        Ok(Some(uaddr).filter(|uaddr| !uaddr.is_empty()))
+    }
+
+    pub fn dump(&self) -> RpcResult<Vec<Rpcb>> {
+        Ok(self
+            .client
+            .call::<_, RpcbList>(RPCBPROC_DUMP, &())?
+            .into_inner())
+    }
+
+    /// register version `vers` of program `prog` as listening at `addr` on `netid`
+    pub fn register(&self, prog: u32, vers: u32, netid: &str, addr: SocketAddr) -> RpcResult<bool> {
+        self.set(&Rpcb {
+            r_prog: prog,
+            r_vers: vers,
+            r_netid: netid.to_string(),
+            r_addr: UniversalAddr::from(addr).to_string(),
+            r_owner: String::new(),
+        })
+    }
+
+    /// address version `vers` of program `prog` listens at on `netid`, if
+    /// registered; a malformed registered address fails with
+    /// `Error::InvalidUniversalAddr`
+    pub fn lookup(&self, prog: u32, vers: u32, netid: &str) -> RpcResult<Option<SocketAddr>> {
+        match self.getaddr(prog, vers, netid)? {
+            Some(uaddr) => Ok(Some(uaddr.parse::<UniversalAddr>()?.into())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Client of portmapper version 2.
+pub struct PortmapClient {
+    client: RpcClient,
+}
+
+impl PortmapClient {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(PortmapClient {
+            client: RpcClient::connect(addr, PMAP_PROG, PMAP_VERS)?,
+        })
+    }
+
+    pub fn set(&self, mapping: &Mapping) -> RpcResult<bool> {
+        self.client.call(PMAPPROC_SET, mapping)
+    }
+
+    pub fn unset(&self, mapping: &Mapping) -> RpcResult<bool> {
+        self.client.call(PMAPPROC_UNSET, mapping)
+    }
+
+    /// port of the program, 0 if it is not registered; a reply beyond the
+    /// range of ports fails with `Error::IntegerOutOfRange`
+    pub fn getport(&self, prog: u32, vers: u32, prot: u32) -> RpcResult<u16> {
+        let mapping = Mapping {
+            prog,
+            vers,
+            prot,
+            port: 0,
+        };
+        let port: u32 = self.client.call(PMAPPROC_GETPORT, &mapping)?;
+        u16::try_from(port).map_err(|_| {
+            RpcError::Xdr(Error::IntegerOutOfRange {
+                expected_type: "u16",
+                value: i64::from(port),
+            })
+        })
+    }
+
+    pub fn dump(&self) -> RpcResult<Vec<Mapping>> {
+        Ok(self
+            .client
+            .call::<_, PmapList>(PMAPPROC_DUMP, &())?
+            .into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_bytes, to_bytes};
+
+    #[test]
+    fn test_portmap_wire() {
+        let list: PmapList = vec![Mapping {
+            prog: 100003,
+            vers: 3,
+            prot: IPPROTO_TCP,
+            port: 2049,
+        }]
+        .into();
+        let bytes = to_bytes(&list).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0, 0, 0, 1, // value follows
+                0, 1, 0x86, 0xa3, // prog
+                0, 0, 0, 3, // vers
+                0, 0, 0, 6, // prot
+                0, 0, 0x08, 0x01, // port
+                0, 0, 0, 0, // no next
+            ]
+        );
+        assert_eq!(from_bytes::<PmapList>(&bytes).unwrap(), list);
+
+        let rpcb = Rpcb {
+            r_prog: 100005,
+            r_vers: 3,
+            r_netid: "udp".to_string(),
+            r_addr: "10.0.0.1.2.241".to_string(),
+            r_owner: "0".to_string(),
+        };
+        let bytes = to_bytes(&rpcb).unwrap();
+        assert_eq!(bytes[8..16], [0, 0, 0, 3, b'u', b'd', b'p', 0]);
+        assert_eq!(from_bytes::<Rpcb>(&bytes).unwrap(), rpcb);
+    }
+
+    #[test]
+    fn test_rpcbind_stand_in() {
+        let rpcbind = Rpcbind::new();
+        let addr = rpcbind.spawn_local().unwrap();
+        let client = RpcbindClient::connect(addr).unwrap();
+
+        let service: SocketAddr = "127.0.0.1:20049".parse().unwrap();
+        assert!(client.register(100003, 3, "tcp", service).unwrap());
+        assert!(!client.register(100003, 3, "tcp", service).unwrap());
+        assert!(client.register(100003, 3, "udp", service).unwrap());
+        assert_eq!(client.lookup(100003, 3, "tcp").unwrap(), Some(service));
+        assert_eq!(client.lookup(100003, 4, "tcp").unwrap(), None);
+        assert_eq!(client.dump().unwrap().len(), 2);
+        assert_eq!(rpcbind.registrations().len(), 2);
+
+        let portmap = PortmapClient::connect(addr).unwrap();
+        assert_eq!(portmap.getport(100003, 3, IPPROTO_UDP).unwrap(), 20049);
+        let mount = Mapping {
+            prog: 100005,
+            vers: 3,
+            prot: IPPROTO_TCP,
+            port: 20048,
+        };
+        assert!(portmap.set(&mount).unwrap());
+        assert_eq!(
+            client
+                .lookup(100005, 3, "tcp")
+                .unwrap()
+                .map(|addr| addr.port()),
+            Some(20048)
+        );
+        assert_eq!(portmap.dump().unwrap().len(), 3);
+
+        let nfs = Rpcb {
+            r_prog: 100003,
+            r_vers: 3,
+            ..Default::default()
+        };
+        assert!(client.unset(&nfs).unwrap());
+        assert!(!client.unset(&nfs).unwrap());
+        assert!(portmap.unset(&mount).unwrap());
+        assert!(client.dump().unwrap().is_empty());
+
+        rpcbind.set(Rpcb {
+            r_prog: 100021,
+            r_vers: 4,
+            r_netid: "tcp".to_string(),
+            r_addr: "127.0.0.1.8".to_string(),
+            r_owner: String::new(),
+        });
+        assert!(matches!(
+            client.lookup(100021, 4, "tcp"),
+            Err(RpcError::Xdr(Error::InvalidUniversalAddr(_)))
+        ));
+    }
+
+    #[test]
+    fn test_portmap_getport_out_of_range() {
+        let mut server = RpcServer::new();
+        server.register(PMAP_PROG, PMAP_VERS, PMAPPROC_GETPORT, |_, _: Mapping| {
+            Ok(70000u32)
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || server.serve_tcp(&listener));
+
+        let portmap = PortmapClient::connect(addr).unwrap();
+        assert!(matches!(
+            portmap.getport(100003, 3, IPPROTO_TCP),
+            Err(RpcError::Xdr(Error::IntegerOutOfRange {
+                expected_type: "u16",
+                value: 70000,
+            }))
+        ));
+    }
+}
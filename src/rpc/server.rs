@@ -0,0 +1,612 @@
+//! Threaded ONC RPC server over record-marked stream transports and UDP.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::cache::ReplyCache;
+use super::{
+    AcceptedReply, AuthStat, CallBody, MessageBody, OpaqueAuth, RPC_VERSION, RejectedReply,
+    ReplyBody, ReplyData, RpcMessage,
+};
+use crate::error::Result;
+use crate::record_marking::{DEFAULT_MAX_RECORD_LEN, RecordReader, RecordWriter};
+use crate::{DecodeLimits, XDRDeserializer, from_bytes_prefix, to_bytes, to_bytes_into};
+
+/// Failure a procedure reports instead of results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcError {
+    /// reply `SYSTEM_ERR`, e.g. on memory allocation failure
+    SystemErr,
+    /// deny the call with `AUTH_ERROR`
+    Auth(AuthStat),
+}
+
+/// largest datagram `serve_udp` accepts unless told otherwise
+pub const DEFAULT_MAX_DATAGRAM_LEN: usize = 65535;
+
+/// pause after a failed accept or receive before trying again, so that a
+/// lasting failure does not spin the thread
+const ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// number of replies kept for retransmitted non-idempotent calls unless told otherwise
+pub const DEFAULT_REPLY_CACHE_SIZE: usize = 1024;
+
+/// handler with its argument and result types erased, decoding the arguments
+/// within the given limits and returning the encoded results or the reply to
+/// send instead
+type Handler = Box<
+    dyn Fn(&CallBody, &[u8], DecodeLimits) -> std::result::Result<Vec<u8>, ReplyBody> + Send + Sync,
+>;
+
+/// Dispatches calls to handlers registered per program, version and procedure.
+///
+/// The NULL procedure (0) of every registered version is answered without a
+/// handler. Calls to unregistered programs, versions or procedures get
+/// `PROG_UNAVAIL`, `PROG_MISMATCH` or `PROC_UNAVAIL`, and arguments that do
+/// not decode get `GARBAGE_ARGS`.
+pub struct RpcServer {
+    handlers: BTreeMap<(u32, u32, u32), Handler>,
+    non_idempotent: BTreeSet<(u32, u32, u32)>,
+    max_record_len: usize,
+    max_datagram_len: usize,
+    limits: DecodeLimits,
+    reply_cache: Mutex<ReplyCache>,
+}
+
+impl Default for RpcServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RpcServer {
+    pub fn new() -> Self {
+        RpcServer {
+            handlers: BTreeMap::new(),
+            non_idempotent: BTreeSet::new(),
+            max_record_len: DEFAULT_MAX_RECORD_LEN,
+            max_datagram_len: DEFAULT_MAX_DATAGRAM_LEN,
+            limits: DecodeLimits::default(),
+            reply_cache: Mutex::new(ReplyCache::new(DEFAULT_REPLY_CACHE_SIZE)),
+        }
+    }
+
+    /// refuse, and drop the connection on, records longer than `len` bytes
+    pub fn with_max_record_len(mut self, len: usize) -> Self {
+        self.max_record_len = len;
+        self
+    }
+
+    /// drop datagrams longer than `len` bytes unanswered, and answer calls
+    /// whose reply would be longer with `SYSTEM_ERR`
+    pub fn with_max_datagram_len(mut self, len: usize) -> Self {
+        self.max_datagram_len = len;
+        self
+    }
+
+    /// decode call arguments within `limits`, answering those beyond them with
+    /// `GARBAGE_ARGS`; `DecodeLimits::default()` unless told otherwise, which
+    /// bounds the nesting depth
+    pub fn with_decode_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// keep the replies of the last `size` non-idempotent calls received over UDP
+    pub fn with_reply_cache_size(mut self, size: usize) -> Self {
+        self.reply_cache.get_mut().unwrap().set_capacity(size);
+        self
+    }
+
+    /// call `handler` for procedure `proc` of version `vers` of program `prog`,
+    /// replacing the handler registered before, if any
+    pub fn register<A, R, F>(&mut self, prog: u32, vers: u32, proc: u32, handler: F) -> &mut Self
+    where
+        A: DeserializeOwned,
+        R: Serialize,
+        F: Fn(&CallBody, A) -> std::result::Result<R, ProcError> + Send + Sync + 'static,
+    {
+        let handler: Handler = Box::new(move |call, args, limits| {
+            let mut deserializer = XDRDeserializer::from_bytes(args).with_limits(limits);
+            let args = A::deserialize(&mut deserializer)
+                .and_then(|args| deserializer.end().map(|()| args))
+                .map_err(|_| accepted(ReplyData::GarbageArgs))?;
+            match handler(call, args) {
+                Ok(results) => to_bytes(&results).map_err(|_| accepted(ReplyData::SystemErr)),
+                Err(ProcError::SystemErr) => Err(accepted(ReplyData::SystemErr)),
+                Err(ProcError::Auth(stat)) => {
+                    Err(ReplyBody::Denied(RejectedReply::AuthError(stat)))
+                }
+            }
+        });
+        self.handlers.insert((prog, vers, proc), handler);
+        self.non_idempotent.remove(&(prog, vers, proc));
+        self
+    }
+
+    /// like `register`, for procedures that must not run twice for one call,
+    /// such as those creating or removing something; when such a call is
+    /// retransmitted over UDP, the cached reply is sent again instead
+    pub fn register_non_idempotent<A, R, F>(
+        &mut self,
+        prog: u32,
+        vers: u32,
+        proc: u32,
+        handler: F,
+    ) -> &mut Self
+    where
+        A: DeserializeOwned,
+        R: Serialize,
+        F: Fn(&CallBody, A) -> std::result::Result<R, ProcError> + Send + Sync + 'static,
+    {
+        self.register(prog, vers, proc, handler);
+        self.non_idempotent.insert((prog, vers, proc));
+        self
+    }
+
+    /// answer one call record, returning the reply record, or `None` for
+    /// records that are not a call and get no reply
+    pub fn handle_record(&self, record: &[u8]) -> Option<Vec<u8>> {
+        let (message, args) = from_bytes_prefix::<RpcMessage>(record).ok()?;
+        let MessageBody::Call(call) = message.body else {
+            return None;
+        };
+        self.reply(message.xid, self.dispatch(&call, args))
+    }
+
+    /// answer one call datagram from `peer`, replaying the cached reply
+    /// when a non-idempotent call is received again
+    fn handle_datagram(&self, datagram: &[u8], peer: SocketAddr) -> Option<Vec<u8>> {
+        let (message, args) = from_bytes_prefix::<RpcMessage>(datagram).ok()?;
+        let MessageBody::Call(call) = message.body else {
+            return None;
+        };
+        let cached = self
+            .non_idempotent
+            .contains(&(call.prog, call.vers, call.proc))
+            .then_some((message.xid, peer, call.prog, call.proc));
+        if let Some(key) = &cached
+            && let Some(reply) = self.reply_cache.lock().unwrap().get(key)
+        {
+            return Some(reply.to_vec());
+        }
+        let mut reply = self.reply(message.xid, self.dispatch(&call, args))?;
+        if reply.len() > self.max_datagram_len {
+            reply = self.reply(message.xid, Err(accepted(ReplyData::SystemErr)))?;
+        }
+        if let Some(key) = cached {
+            self.reply_cache.lock().unwrap().insert(key, reply.clone());
+        }
+        Some(reply)
+    }
+
+    /// encode the reply to call `xid`, with its results on success
+    fn reply(&self, xid: u32, outcome: std::result::Result<Vec<u8>, ReplyBody>) -> Option<Vec<u8>> {
+        let (body, results) = match outcome {
+            Ok(results) => (accepted(ReplyData::Success), results),
+            Err(body) => (body, Vec::new()),
+        };
+        let header = RpcMessage {
+            xid,
+            body: MessageBody::Reply(body),
+        };
+        let mut reply = Vec::new();
+        to_bytes_into(&header, &mut reply).ok()?;
+        reply.extend_from_slice(&results);
+        Some(reply)
+    }
+
+    fn dispatch(&self, call: &CallBody, args: &[u8]) -> std::result::Result<Vec<u8>, ReplyBody> {
+        if call.rpcvers != RPC_VERSION {
+            return Err(ReplyBody::Denied(RejectedReply::RpcMismatch {
+                low: RPC_VERSION,
+                high: RPC_VERSION,
+            }));
+        }
+        let mut versions = self
+            .handlers
+            .range((call.prog, 0, 0)..=(call.prog, u32::MAX, u32::MAX))
+            .map(|(&(_, vers, _), _)| vers);
+        let Some(low) = versions.next() else {
+            return Err(accepted(ReplyData::ProgUnavail));
+        };
+        let high = versions.next_back().unwrap_or(low);
+        let mut procs = self
+            .handlers
+            .range((call.prog, call.vers, 0)..=(call.prog, call.vers, u32::MAX))
+            .peekable();
+        if procs.peek().is_none() {
+            return Err(accepted(ReplyData::ProgMismatch { low, high }));
+        }
+        match self.handlers.get(&(call.prog, call.vers, call.proc)) {
+            Some(handler) => handler(call, args, self.limits),
+            None if call.proc == 0 => Ok(Vec::new()),
+            None => Err(accepted(ReplyData::ProcUnavail)),
+        }
+    }
+
+    /// answer the calls read from `reader` on `writer`, until `reader` ends
+    pub fn serve_connection<R, W>(&self, reader: R, writer: W) -> Result<()>
+    where
+        R: io::Read,
+        W: Write,
+    {
+        let mut reader = RecordReader::new(reader).with_max_record_len(self.max_record_len);
+        let mut writer = RecordWriter::new(writer);
+        while let Some(record) = reader.read_record()? {
+            if let Some(reply) = self.handle_record(record) {
+                writer.write_record(&reply)?;
+                writer.get_mut().flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// serve each connection accepted on `listener` on its own thread; never returns
+    pub fn serve_tcp(&self, listener: &TcpListener) {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                // such as running out of file descriptors, which may last a while
+                let Ok(stream) = stream else {
+                    thread::sleep(ERROR_BACKOFF);
+                    continue;
+                };
+                scope.spawn(move || {
+                    self.serve_connection(BufReader::new(&stream), BufWriter::new(&stream))
+                });
+            }
+        })
+    }
+
+    /// answer the call datagrams received on `socket`, one at a time; never returns
+    pub fn serve_udp(&self, socket: &UdpSocket) {
+        // one spare byte tells datagrams over the limit from those right at it
+        let mut buf = vec![0; self.max_datagram_len.saturating_add(1).min(1 << 16)];
+        loop {
+            let (len, peer) = match socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                // an ICMP port unreachable from an earlier reply, reported once
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::Interrupted | io::ErrorKind::ConnectionRefused
+                    ) =>
+                {
+                    continue;
+                }
+                Err(_) => {
+                    thread::sleep(ERROR_BACKOFF);
+                    continue;
+                }
+            };
+            if len > self.max_datagram_len {
+                continue;
+            }
+            if let Some(reply) = self.handle_datagram(&buf[..len], peer) {
+                let _ = socket.send_to(&reply, peer);
+            }
+        }
+    }
+
+    /// serve each connection accepted on `listener` on its own thread; never returns
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: &UnixListener) {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                // such as running out of file descriptors, which may last a while
+                let Ok(stream) = stream else {
+                    thread::sleep(ERROR_BACKOFF);
+                    continue;
+                };
+                scope.spawn(move || {
+                    self.serve_connection(BufReader::new(&stream), BufWriter::new(&stream))
+                });
+            }
+        })
+    }
+}
+
+fn accepted(reply_data: ReplyData) -> ReplyBody {
+    ReplyBody::Accepted(AcceptedReply {
+        verf: OpaqueAuth::none(),
+        reply_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpStream;
+
+    use super::*;
+    use crate::from_bytes;
+    use crate::rpc::AuthSysParams;
+
+    const PROG: u32 = 0x2000_0001;
+
+    fn server() -> RpcServer {
+        let mut server = RpcServer::new();
+        server
+            .register(PROG, 1, 1, |_, (a, b): (u32, u32)| Ok(a + b))
+            .register(PROG, 2, 1, |_, (a, b): (u32, u32)| Ok(a * b))
+            .register(PROG, 2, 2, |call, ()| match call.auth_sys() {
+                Ok(params) => Ok(params.uid),
+                Err(_) => Err(ProcError::Auth(AuthStat::AuthTooweak)),
+            })
+            .register(PROG, 2, 3, |_, ()| Err::<(), _>(ProcError::SystemErr));
+        server
+    }
+
+    fn call<A: Serialize>(server: &RpcServer, call: CallBody, args: &A) -> (RpcMessage, Vec<u8>) {
+        let mut record = Vec::new();
+        to_bytes_into(&RpcMessage::call(9, call), &mut record).unwrap();
+        to_bytes_into(args, &mut record).unwrap();
+        let reply = server.handle_record(&record).unwrap();
+        let (message, results) = from_bytes_prefix::<RpcMessage>(&reply).unwrap();
+        assert_eq!(message.xid, 9);
+        (message, results.to_vec())
+    }
+
+    #[test]
+    fn test_server_dispatch() {
+        let server = server();
+        let (message, results) = call(&server, CallBody::new(PROG, 1, 1), &(2u32, 3u32));
+        assert_eq!(message, RpcMessage::accepted(9, ReplyData::Success));
+        assert_eq!(from_bytes::<u32>(&results).unwrap(), 5);
+
+        let (_, results) = call(&server, CallBody::new(PROG, 2, 1), &(2u32, 3u32));
+        assert_eq!(from_bytes::<u32>(&results).unwrap(), 6);
+
+        let (message, results) = call(&server, CallBody::new(PROG, 1, 0), &());
+        assert_eq!(message, RpcMessage::accepted(9, ReplyData::Success));
+        assert!(results.is_empty());
+
+        let params = AuthSysParams {
+            uid: 1000,
+            ..Default::default()
+        };
+        let auth_call = CallBody::new(PROG, 2, 2).with_auth_sys(&params).unwrap();
+        let (_, results) = call(&server, auth_call, &());
+        assert_eq!(from_bytes::<u32>(&results).unwrap(), 1000);
+    }
+
+    #[test]
+    fn test_server_error_replies() {
+        let server = server();
+        let expect = |body: CallBody, reply: RpcMessage| {
+            assert_eq!(call(&server, body, &()).0, reply);
+        };
+        expect(
+            CallBody::new(PROG + 1, 1, 0),
+            RpcMessage::accepted(9, ReplyData::ProgUnavail),
+        );
+        expect(
+            CallBody::new(PROG, 3, 1),
+            RpcMessage::accepted(9, ReplyData::ProgMismatch { low: 1, high: 2 }),
+        );
+        expect(
+            CallBody::new(PROG, 1, 7),
+            RpcMessage::accepted(9, ReplyData::ProcUnavail),
+        );
+        expect(
+            CallBody::new(PROG, 2, 3),
+            RpcMessage::accepted(9, ReplyData::SystemErr),
+        );
+        expect(
+            CallBody::new(PROG, 2, 2),
+            RpcMessage::denied(9, RejectedReply::AuthError(AuthStat::AuthTooweak)),
+        );
+        let mut body = CallBody::new(PROG, 1, 0);
+        body.rpcvers = 3;
+        expect(
+            body,
+            RpcMessage::denied(9, RejectedReply::RpcMismatch { low: 2, high: 2 }),
+        );
+
+        // a single u32 where (u32, u32) is expected
+        let (message, _) = call(&server, CallBody::new(PROG, 1, 1), &1u32);
+        assert_eq!(message, RpcMessage::accepted(9, ReplyData::GarbageArgs));
+
+        let reply = to_bytes(&RpcMessage::accepted(1, ReplyData::Success)).unwrap();
+        assert!(server.handle_record(&reply).is_none());
+        assert!(server.handle_record(&[0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_server_decode_limits() {
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        struct Node {
+            #[allow(dead_code)]
+            next: Option<Box<Node>>,
+        }
+
+        // a list of `len` nodes, each nesting two levels deeper
+        let call_list = |server: &RpcServer, len: usize| {
+            let mut record = to_bytes(&RpcMessage::call(9, CallBody::new(PROG, 1, 1))).unwrap();
+            record.extend_from_slice(&[0, 0, 0, 1].repeat(len));
+            record.extend_from_slice(&[0, 0, 0, 0]);
+            let reply = server.handle_record(&record).unwrap();
+            from_bytes_prefix::<RpcMessage>(&reply).unwrap().0
+        };
+        let mut server = RpcServer::new();
+        server.register(PROG, 1, 1, |_, _: Option<Box<Node>>| Ok(()));
+
+        // far deeper than the stack could take, yet within the record limit
+        assert!(100_000 * 4 < DEFAULT_MAX_RECORD_LEN);
+        assert_eq!(
+            call_list(&server, 100_000),
+            RpcMessage::accepted(9, ReplyData::GarbageArgs)
+        );
+        assert_eq!(
+            call_list(&server, 3),
+            RpcMessage::accepted(9, ReplyData::Success)
+        );
+
+        let server = server.with_decode_limits(DecodeLimits {
+            max_depth: 4,
+            ..Default::default()
+        });
+        assert_eq!(
+            call_list(&server, 3),
+            RpcMessage::accepted(9, ReplyData::GarbageArgs)
+        );
+        assert_eq!(
+            call_list(&server, 0),
+            RpcMessage::accepted(9, ReplyData::Success)
+        );
+    }
+
+    fn round_trip<S: io::Read + Write>(mut stream: S) {
+        let mut writer = RecordWriter::new(Vec::new())
+            .with_max_fragment_len(16)
+            .unwrap();
+        for xid in 1..=2 {
+            let mut record = Vec::new();
+            to_bytes_into(
+                &RpcMessage::call(xid, CallBody::new(PROG, 1, 1)),
+                &mut record,
+            )
+            .unwrap();
+            to_bytes_into(&(xid, 40u32), &mut record).unwrap();
+            writer.write_record(&record).unwrap();
+        }
+        stream.write_all(&writer.into_inner()).unwrap();
+
+        let mut reader = RecordReader::new(stream);
+        for xid in 1..=2 {
+            let record = reader.read_record().unwrap().unwrap();
+            let (message, results) = from_bytes_prefix::<RpcMessage>(record).unwrap();
+            assert_eq!(message, RpcMessage::accepted(xid, ReplyData::Success));
+            assert_eq!(from_bytes::<u32>(results).unwrap(), 40 + xid);
+        }
+    }
+
+    #[test]
+    fn test_server_max_record_len() {
+        use crate::{Error, LimitKind};
+
+        // a peer announcing a record far longer than it sends
+        let wire = [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+        let result = server().serve_connection(&wire[..], Vec::new());
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::RecordLen,
+                limit: DEFAULT_MAX_RECORD_LEN,
+                ..
+            })
+        ));
+
+        let server = server().with_max_record_len(4);
+        let wire = [0x80, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0];
+        let result = server.serve_connection(&wire[..], Vec::new());
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::RecordLen,
+                limit: 4,
+                actual: 8,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_server_tcp() {
+        let server = server();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || server.serve_tcp(&listener));
+        round_trip(TcpStream::connect(addr).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_server_unix() {
+        use std::os::unix::net::UnixStream;
+
+        let path = std::env::temp_dir().join(format!("xdr_brk_rpc_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = server();
+        thread::spawn(move || server.serve_unix(&listener));
+        round_trip(UnixStream::connect(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_server_udp_reply_cache() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+
+        let created = Arc::new(AtomicU32::new(0));
+        let looked_up = Arc::new(AtomicU32::new(0));
+        let mut server = RpcServer::new().with_max_datagram_len(128);
+        let counter = Arc::clone(&created);
+        server.register_non_idempotent(PROG, 1, 1, move |_, ()| {
+            Ok(counter.fetch_add(1, Ordering::SeqCst) + 1)
+        });
+        let counter = Arc::clone(&looked_up);
+        server.register(PROG, 1, 2, move |_, ()| {
+            Ok(counter.fetch_add(1, Ordering::SeqCst) + 1)
+        });
+        server.register(PROG, 1, 3, |_, len: u32| Ok(vec![0u32; len as usize]));
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        thread::spawn(move || server.serve_udp(&socket));
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let call = |xid: u32, proc: u32, args: &[u8]| {
+            let mut datagram =
+                to_bytes(&RpcMessage::call(xid, CallBody::new(PROG, 1, proc))).unwrap();
+            datagram.extend_from_slice(args);
+            client.send(&datagram).unwrap();
+            let mut buf = [0; 256];
+            let len = client.recv(&mut buf).unwrap();
+            let (message, results) = from_bytes_prefix::<RpcMessage>(&buf[..len]).unwrap();
+            assert_eq!(message.xid, xid);
+            (message, results.to_vec())
+        };
+
+        // a retransmitted create gets the first reply again without running twice
+        let (_, first) = call(10, 1, &[]);
+        let (_, again) = call(10, 1, &[]);
+        assert_eq!(first, again);
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        let (_, next) = call(11, 1, &[]);
+        assert_eq!(from_bytes::<u32>(&next).unwrap(), 2);
+
+        // idempotent procedures just run again
+        call(20, 2, &[]);
+        call(20, 2, &[]);
+        assert_eq!(looked_up.load(Ordering::SeqCst), 2);
+
+        // replies over the datagram limit become SYSTEM_ERR
+        let (message, _) = call(30, 3, &2u32.to_be_bytes());
+        assert_eq!(message, RpcMessage::accepted(30, ReplyData::Success));
+        let (message, _) = call(31, 3, &100u32.to_be_bytes());
+        assert_eq!(message, RpcMessage::accepted(31, ReplyData::SystemErr));
+
+        // datagrams over the limit get no reply
+        let mut datagram = to_bytes(&RpcMessage::call(40, CallBody::new(PROG, 1, 2))).unwrap();
+        datagram.resize(129, 0);
+        client.send(&datagram).unwrap();
+        let (_, results) = call(41, 2, &[]);
+        assert_eq!(from_bytes::<u32>(&results).unwrap(), 3);
+    }
+}
@@ -1,38 +1,150 @@
+use std::io::Write;
+
 use serde::{Serialize, ser};
 
 use crate::{
     PADDING_BYTES,
-    error::{Error, Result},
+    error::{Error, LimitKind, Result},
+    limits::check_limit,
     padding_len,
+    size::SizeCounter,
 };
 
-pub struct XDRSerializer {
-    pub(crate) output: Vec<u8>,
+/// XDR serializer writing into any `std::io::Write` sink, `Vec<u8>` by default.
+pub struct XDRSerializer<W = Vec<u8>> {
+    writer: W,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<W: Write> XDRSerializer<W> {
+    pub fn new(writer: W) -> Self {
+        XDRSerializer {
+            writer,
+            depth: 0,
+            max_depth: usize::MAX,
+        }
+    }
+
+    /// fail with `Error::LimitExceeded` when values nest deeper than `max_depth`;
+    /// unlimited by default, since encoded values are local and trusted
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        check_limit(LimitKind::Depth, self.max_depth, self.depth + 1)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// undo `enter`, also when the nested value failed, so that the depth
+    /// stays right for whatever the serializer is used for next
+    fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// serialize `value` one level deeper than the current one
+    fn nested<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.enter()?;
+        let result = value.serialize(&mut *self);
+        self.leave();
+        result
+    }
+
+    /// serialize an element of a compound value `enter`ed before, leaving it
+    /// when the element fails, as `end` will not be called then
+    fn element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self).inspect_err(|_| self.leave())
+    }
+
+    /// serializer for map keys and values, continuing at the current depth
+    fn buffer(&self) -> XDRSerializer<Vec<u8>> {
+        XDRSerializer {
+            writer: Vec::new(),
+            depth: self.depth,
+            max_depth: self.max_depth,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// write a u32 length prefix, refusing lengths it cannot hold
+    fn write_len(&mut self, len: usize) -> Result<()> {
+        let len = u32::try_from(len).map_err(|_| Error::LengthOverflow)?;
+        self.write(&len.to_be_bytes())
+    }
 }
 
 pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
 where
     T: Serialize,
 {
-    let mut serializer = XDRSerializer { output: Vec::new() };
+    let mut serializer = XDRSerializer::new(Vec::new());
     value.serialize(&mut serializer)?;
-    Ok(serializer.output)
+    Ok(serializer.into_inner())
 }
 
-impl<'a> ser::Serializer for &'a mut XDRSerializer {
+/// serialize `value` straight into `writer`, without staging the whole message in memory
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: Write,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = XDRSerializer::new(writer);
+    value.serialize(&mut serializer)
+}
+
+/// append the encoding of `value` to `buf`, leaving `buf` as it was on error
+pub fn to_bytes_into<T>(value: &T, buf: &mut Vec<u8>) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    let len = buf.len();
+    to_writer(&mut *buf, value).inspect_err(|_| buf.truncate(len))
+}
+
+/// encode `value` at the start of `buf` and return the number of bytes written,
+/// failing with `Error::BufferTooSmall` rather than writing a partial encoding
+pub fn to_slice<T>(value: &T, buf: &mut [u8]) -> Result<usize>
+where
+    T: ?Sized + Serialize,
+{
+    let needed = serialize_len(value)?;
+    if needed > buf.len() {
+        return Err(Error::BufferTooSmall { needed });
+    }
+    to_writer(&mut buf[..needed], value)?;
+    Ok(needed)
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
-    type SerializeMap = MapSerializer<'a>;
+    type SerializeMap = MapSerializer<'a, W>;
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend((v as u32).to_be_bytes());
-        Ok(())
+        self.write(&(v as u32).to_be_bytes())
     }
     fn serialize_i8(self, v: i8) -> std::result::Result<Self::Ok, Self::Error> {
         self.serialize_i32(v as i32)
@@ -41,12 +153,10 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         self.serialize_i32(v as i32)
     }
     fn serialize_i32(self, v: i32) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(v.to_be_bytes());
-        Ok(())
+        self.write(&v.to_be_bytes())
     }
     fn serialize_i64(self, v: i64) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(v.to_be_bytes());
-        Ok(())
+        self.write(&v.to_be_bytes())
     }
 
     fn serialize_u8(self, v: u8) -> std::result::Result<Self::Ok, Self::Error> {
@@ -56,22 +166,18 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         self.serialize_u32(v as u32)
     }
     fn serialize_u32(self, v: u32) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(v.to_be_bytes());
-        Ok(())
+        self.write(&v.to_be_bytes())
     }
 
     fn serialize_u64(self, v: u64) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(v.to_be_bytes());
-        Ok(())
+        self.write(&v.to_be_bytes())
     }
 
     fn serialize_f32(self, v: f32) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(v.to_be_bytes());
-        Ok(())
+        self.write(&v.to_be_bytes())
     }
     fn serialize_f64(self, v: f64) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(v.to_be_bytes());
-        Ok(())
+        self.write(&v.to_be_bytes())
     }
 
     fn serialize_char(self, v: char) -> std::result::Result<Self::Ok, Self::Error> {
@@ -79,16 +185,15 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
     }
 
     fn serialize_none(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend((0 as u32).to_be_bytes());
-        Ok(())
+        self.write(&0u32.to_be_bytes())
     }
 
     fn serialize_some<T>(self, value: &T) -> std::result::Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        self.output.extend((1 as u32).to_be_bytes());
-        value.serialize(self)
+        self.write(&1u32.to_be_bytes())?;
+        self.nested(value)
     }
 
     /// do noting for unit
@@ -100,23 +205,17 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         let len = v.len();
         let padding_len = padding_len(len);
 
-        self.output.extend((len as u32).to_be_bytes());
-        self.output.extend(v.as_bytes());
-        self.output.extend(&PADDING_BYTES[..padding_len]);
-
-        Ok(())
+        self.write_len(len)?;
+        self.write(v.as_bytes())?;
+        self.write(&PADDING_BYTES[..padding_len])
     }
 
     fn serialize_bytes(self, v: &[u8]) -> std::result::Result<Self::Ok, Self::Error> {
         let bytes_len = v.len();
-        if bytes_len > u32::MAX as usize {
-            return Err(Error::Message("bytes too long".to_string()));
-        }
-        self.output.extend((bytes_len as u32).to_be_bytes());
+        self.write_len(bytes_len)?;
         let padding_len = padding_len(bytes_len);
-        self.output.extend(v);
-        self.output.extend(&PADDING_BYTES[..padding_len]);
-        Ok(())
+        self.write(v)?;
+        self.write(&PADDING_BYTES[..padding_len])
     }
 
     /// do noting
@@ -135,8 +234,7 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         variant_index: u32,
         _variant: &'static str,
     ) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.extend(variant_index.to_be_bytes());
-        Ok(())
+        self.write(&variant_index.to_be_bytes())
     }
 
     fn serialize_newtype_variant<T>(
@@ -149,8 +247,8 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
     where
         T: ?Sized + Serialize,
     {
-        self.output.extend(variant_index.to_be_bytes());
-        value.serialize(self)
+        self.write(&variant_index.to_be_bytes())?;
+        self.nested(value)
     }
 
     fn serialize_tuple_variant(
@@ -160,7 +258,8 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         _variant: &'static str,
         _len: usize,
     ) -> std::result::Result<Self::SerializeTupleVariant, Self::Error> {
-        self.output.extend(variant_index.to_be_bytes());
+        self.write(&variant_index.to_be_bytes())?;
+        self.enter()?;
         Ok(self)
     }
 
@@ -172,7 +271,8 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         _variant: &'static str,
         _len: usize,
     ) -> std::result::Result<Self::SerializeStructVariant, Self::Error> {
-        self.output.extend(variant_index.to_be_bytes());
+        self.write(&variant_index.to_be_bytes())?;
+        self.enter()?;
         Ok(self)
     }
 
@@ -191,8 +291,9 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         self,
         len: Option<usize>,
     ) -> std::result::Result<Self::SerializeSeq, Self::Error> {
-        let len = len.ok_or(Self::Error::SequenceWithoutLength)? as u32;
-        self.output.extend(len.to_be_bytes());
+        let len = len.ok_or(Self::Error::SequenceWithoutLength)?;
+        self.write_len(len)?;
+        self.enter()?;
         Ok(self)
     }
 
@@ -201,6 +302,7 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         self,
         _len: usize,
     ) -> std::result::Result<Self::SerializeTuple, Self::Error> {
+        self.enter()?;
         Ok(self)
     }
 
@@ -210,6 +312,7 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         _name: &'static str,
         _len: usize,
     ) -> std::result::Result<Self::SerializeTupleStruct, Self::Error> {
+        self.enter()?;
         Ok(self)
     }
 
@@ -218,8 +321,9 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         self,
         len: Option<usize>,
     ) -> std::result::Result<Self::SerializeMap, Self::Error> {
-        let len = len.ok_or(Self::Error::SequenceWithoutLength)? as u32;
-        self.output.extend(len.to_be_bytes());
+        let len = len.ok_or(Self::Error::SequenceWithoutLength)?;
+        self.write_len(len)?;
+        self.enter()?;
         Ok(MapSerializer {
             serializer: self,
             current_key: None,
@@ -232,87 +336,101 @@ impl<'a> ser::Serializer for &'a mut XDRSerializer {
         _name: &'static str,
         _len: usize,
     ) -> std::result::Result<Self::SerializeStruct, Self::Error> {
+        self.enter()?;
         Ok(self)
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeSeq for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.element(value)
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeTuple for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.element(value)
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeTupleStruct for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.element(value)
     }
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeTupleVariant for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.element(value)
     }
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
         Ok(())
     }
 }
 
-pub struct MapSerializer<'a> {
-    serializer: &'a mut XDRSerializer,
+pub struct MapSerializer<'a, W> {
+    serializer: &'a mut XDRSerializer<W>,
     current_key: Option<Vec<u8>>,
     kv_pairs: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
-impl<'a> ser::SerializeMap for MapSerializer<'a> {
+impl<W: Write> MapSerializer<'_, W> {
+    /// leave the map on `err`, as `end` will not be called then
+    fn fail(&mut self, err: Error) -> Error {
+        self.serializer.leave();
+        err
+    }
+}
+
+impl<'a, W: Write> ser::SerializeMap for MapSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_key<T>(&mut self, key: &T) -> std::result::Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        let mut serializer = XDRSerializer { output: Vec::new() };
-        key.serialize(&mut serializer)?;
+        let mut serializer = self.serializer.buffer();
+        key.serialize(&mut serializer)
+            .map_err(|err| self.fail(err))?;
         if self.current_key.is_none() {
-            self.current_key = Some(serializer.output);
+            self.current_key = Some(serializer.into_inner());
         } else {
-            return Err(Error::Message("previous key exists".to_owned()));
+            return Err(self.fail(Error::MapKeyWithoutValue));
         }
         Ok(())
     }
@@ -320,29 +438,32 @@ impl<'a> ser::SerializeMap for MapSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let mut serializer = XDRSerializer { output: Vec::new() };
-        value.serialize(&mut serializer)?;
+        let mut serializer = self.serializer.buffer();
+        value
+            .serialize(&mut serializer)
+            .map_err(|err| self.fail(err))?;
         if let Some(key) = self.current_key.take() {
-            self.kv_pairs.push((key, serializer.output));
+            self.kv_pairs.push((key, serializer.into_inner()));
         } else {
-            return Err(Error::Message("no key exists".to_owned()));
+            return Err(self.fail(Error::MapValueWithoutKey));
         }
         Ok(())
     }
     fn end(mut self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serializer.leave();
         if self.current_key.is_some() {
-            return Err(Error::Message("trailing key exists at end".to_owned()));
+            return Err(Error::MapKeyWithoutValue);
         }
         self.kv_pairs.sort_by(|a, b| a.0.cmp(&b.0));
         for (key, value) in self.kv_pairs {
-            self.serializer.output.extend_from_slice(&key);
-            self.serializer.output.extend_from_slice(&value);
+            self.serializer.write(&key)?;
+            self.serializer.write(&value)?;
         }
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeMap for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_key<T>(&mut self, key: &T) -> std::result::Result<(), Self::Error>
@@ -364,7 +485,7 @@ impl<'a> ser::SerializeMap for &'a mut XDRSerializer {
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeStruct for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T>(
@@ -375,14 +496,15 @@ impl<'a> ser::SerializeStruct for &'a mut XDRSerializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.element(value)
     }
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut XDRSerializer {
+impl<'a, W: Write> ser::SerializeStructVariant for &'a mut XDRSerializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T>(
@@ -393,19 +515,22 @@ impl<'a> ser::SerializeStructVariant for &'a mut XDRSerializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.element(value)
     }
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
         Ok(())
     }
 }
 
-pub fn serialize_len(value: &impl Serialize) -> std::result::Result<usize, Error> {
-    let mut serializer = XDRSerializer {
-        output: Vec::new(),
-    };
-    value.serialize(&mut serializer)?;
-    Ok(serializer.output.len())
+/// encoded size of `value`, computed without producing any of its bytes
+pub fn serialize_len<T>(value: &T) -> std::result::Result<usize, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut counter = SizeCounter::new();
+    value.serialize(&mut counter)?;
+    Ok(counter.size())
 }
 
 #[cfg(test)]
@@ -493,4 +618,218 @@ mod tests {
             + 4; // i32 length
         assert_eq!(serialize_len, expected_len);
     }
+
+    #[test]
+    fn test_serialize_to_writer() {
+        use crate::to_writer;
+
+        #[derive(Debug, Serialize)]
+        struct MyStruct {
+            s: String,
+            i: i32,
+        }
+
+        let data = MyStruct {
+            s: "hello".to_string(),
+            i: 42,
+        };
+        let mut writer = std::io::BufWriter::new(Vec::new());
+        to_writer(&mut writer, &data).unwrap();
+        let written = writer.into_inner().unwrap();
+        assert_eq!(written, to_bytes(&data).unwrap());
+    }
+
+    #[test]
+    fn test_serialize_to_writer_with_io_error() {
+        use crate::{Error, to_writer};
+
+        struct FullWriter;
+        impl std::io::Write for FullWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::WriteZero.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let result = to_writer(FullWriter, &42u32);
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_serialize_recursive_type_with_depth_limit() {
+        use crate::{Error, LimitKind, XDRSerializer, serialize_len};
+
+        #[derive(Debug, Serialize)]
+        struct Entry {
+            fileid: u64,
+            next: Option<Box<Entry>>,
+        }
+
+        fn linked_list(len: usize) -> Option<Box<Entry>> {
+            (0..len as u64)
+                .rev()
+                .fold(None, |next, fileid| Some(Box::new(Entry { fileid, next })))
+        }
+
+        // unlimited unless asked for
+        let list = linked_list(1000);
+        assert_eq!(to_bytes(&list).unwrap().len(), 1000 * 12 + 4);
+        assert_eq!(serialize_len(&list).unwrap(), 1000 * 12 + 4);
+
+        // every entry takes two levels, the Option and the struct
+        let mut serializer = XDRSerializer::new(Vec::new()).with_max_depth(1999);
+        let result = list.serialize(&mut serializer);
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                kind: LimitKind::Depth,
+                limit: 1999,
+                actual: 2000,
+            })
+        ));
+        // the failed value leaves no levels behind
+        let short = linked_list(999);
+        short.serialize(&mut serializer).unwrap();
+        let nested = vec![vec![short]];
+        nested.serialize(&mut serializer).unwrap_err();
+        vec![linked_list(999)].serialize(&mut serializer).unwrap();
+
+        let mut serializer = XDRSerializer::new(Vec::new()).with_max_depth(2000);
+        list.serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.into_inner().len(), 1000 * 12 + 4);
+    }
+
+    #[test]
+    fn test_serialize_map_misuse() {
+        use serde::ser::{SerializeMap, Serializer};
+
+        use crate::{Error, XDRSerializer};
+
+        let mut serializer = XDRSerializer::new(Vec::new());
+        let mut map = (&mut serializer).serialize_map(Some(1)).unwrap();
+        map.serialize_key(&1u32).unwrap();
+        let result = map.serialize_key(&2u32);
+        assert!(matches!(result, Err(Error::MapKeyWithoutValue)));
+
+        let mut serializer = XDRSerializer::new(Vec::new());
+        let mut map = (&mut serializer).serialize_map(Some(1)).unwrap();
+        let result = map.serialize_value(&1u32);
+        assert!(matches!(result, Err(Error::MapValueWithoutKey)));
+
+        let mut serializer = XDRSerializer::new(Vec::new());
+        let mut map = (&mut serializer).serialize_map(Some(1)).unwrap();
+        map.serialize_key(&1u32).unwrap();
+        let result = map.end();
+        assert!(matches!(result, Err(Error::MapKeyWithoutValue)));
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_serialize_length_overflow() {
+        use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+
+        use crate::{Error, serialize_len};
+
+        const HUGE: usize = u32::MAX as usize + 1;
+
+        // report a length no u32 prefix can hold, without having the elements
+        struct HugeSeq;
+        impl Serialize for HugeSeq {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_seq(Some(HUGE))?.end()
+            }
+        }
+        struct HugeMap;
+        impl Serialize for HugeMap {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_map(Some(HUGE))?.end()
+            }
+        }
+
+        assert!(matches!(to_bytes(&HugeSeq), Err(Error::LengthOverflow)));
+        assert!(matches!(to_bytes(&HugeMap), Err(Error::LengthOverflow)));
+        assert!(matches!(
+            to_bytes(&vec![HugeSeq]),
+            Err(Error::LengthOverflow)
+        ));
+        assert!(matches!(
+            serialize_len(&HugeSeq),
+            Err(Error::LengthOverflow)
+        ));
+        assert!(matches!(
+            serialize_len(&HugeMap),
+            Err(Error::LengthOverflow)
+        ));
+    }
+
+    /// a `str` or `[u8]` cannot report a length it does not have, so this one
+    /// needs 4 GiB of zeroed memory, only mapped, never written
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    #[ignore = "allocates 4 GiB"]
+    fn test_serialize_str_and_bytes_length_overflow() {
+        use serde::ser::Serializer;
+
+        use crate::{Error, XDRSerializer, serialize_len};
+
+        // the length is checked before any byte is written
+        let huge = vec![0u8; u32::MAX as usize + 1];
+        let bytes = serde_bytes::Bytes::new(&huge);
+        assert!(matches!(to_bytes(&bytes), Err(Error::LengthOverflow)));
+        assert!(matches!(serialize_len(&bytes), Err(Error::LengthOverflow)));
+        let mut serializer = XDRSerializer::new(Vec::new());
+        let result = (&mut serializer).serialize_bytes(&huge);
+        assert!(matches!(result, Err(Error::LengthOverflow)));
+        assert!(serializer.into_inner().is_empty());
+
+        let huge = String::from_utf8(huge).unwrap();
+        assert!(matches!(to_bytes(&huge), Err(Error::LengthOverflow)));
+        assert!(matches!(serialize_len(&huge), Err(Error::LengthOverflow)));
+        let mut serializer = XDRSerializer::new(Vec::new());
+        let result = (&mut serializer).serialize_str(&huge);
+        assert!(matches!(result, Err(Error::LengthOverflow)));
+        assert!(serializer.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_serialize_into_buffer() {
+        use serde::{Serializer, ser::SerializeSeq};
+
+        use crate::{Error, to_bytes_into};
+
+        struct Unsized;
+
+        impl Serialize for Unsized {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_seq(None)?.end()
+            }
+        }
+
+        let mut buf = vec![0xff];
+        to_bytes_into(&"hi", &mut buf).unwrap();
+        to_bytes_into(&7u32, &mut buf).unwrap();
+        assert_eq!(buf, [0xff, 0, 0, 0, 2, b'h', b'i', 0, 0, 0, 0, 0, 7]);
+
+        let result = to_bytes_into(&(1u32, Unsized), &mut buf);
+        assert!(matches!(result, Err(Error::SequenceWithoutLength)));
+        assert_eq!(buf, [0xff, 0, 0, 0, 2, b'h', b'i', 0, 0, 0, 0, 0, 7]);
+    }
+
+    #[test]
+    fn test_serialize_into_slice() {
+        use crate::{Error, to_slice};
+
+        let mut buf = [0xff; 16];
+        let len = to_slice(&(1u32, "a"), &mut buf).unwrap();
+        assert_eq!(len, 12);
+        assert_eq!(buf[..len], [0, 0, 0, 1, 0, 0, 0, 1, b'a', 0, 0, 0]);
+        assert_eq!(buf[len..], [0xff; 4]);
+
+        let mut buf = [0xff; 10];
+        let result = to_slice(&(1u32, "a"), &mut buf);
+        assert!(matches!(result, Err(Error::BufferTooSmall { needed: 12 })));
+        assert_eq!(buf, [0xff; 10]);
+    }
 }
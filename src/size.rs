@@ -0,0 +1,396 @@
+//! Serializer computing the encoded size of a value without producing its bytes,
+//! used by `serialize_len`.
+
+use serde::{Serialize, ser};
+
+use crate::{
+    U32_SIZE, U64_SIZE,
+    error::{Error, Result},
+    padding_len,
+};
+
+/// Walks a value like `XDRSerializer` does, summing the encoded sizes instead of writing them.
+pub(crate) struct SizeCounter {
+    size: usize,
+}
+
+impl SizeCounter {
+    pub(crate) fn new() -> Self {
+        SizeCounter { size: 0 }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    fn add(&mut self, len: usize) -> Result<()> {
+        self.size = self.size.checked_add(len).ok_or(Error::LengthOverflow)?;
+        Ok(())
+    }
+
+    fn add_len(&mut self, len: usize) -> Result<()> {
+        u32::try_from(len).map_err(|_| Error::LengthOverflow)?;
+        self.add(U32_SIZE)
+    }
+
+    fn add_padded(&mut self, len: usize) -> Result<()> {
+        self.add_len(len)?;
+        self.add(len + padding_len(len))
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = MapCounter<'a>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        self.add(U64_SIZE)
+    }
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        self.add(U64_SIZE)
+    }
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        self.add(U64_SIZE)
+    }
+    fn serialize_char(self, _v: char) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.add_padded(v.len())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.add_padded(v.len())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.add(U32_SIZE)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        self.add(U32_SIZE)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.add(U32_SIZE)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        let len = len.ok_or(Error::SequenceWithoutLength)?;
+        self.add_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.add(U32_SIZE)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        let len = len.ok_or(Error::SequenceWithoutLength)?;
+        self.add_len(len)?;
+        Ok(MapCounter {
+            counter: self,
+            key_pending: false,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.add(U32_SIZE)?;
+        Ok(self)
+    }
+}
+
+impl ser::SerializeSeq for &mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut SizeCounter {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Map entries are sorted when encoded, which does not change their total size,
+/// so they are counted in the order they come.
+pub(crate) struct MapCounter<'a> {
+    counter: &'a mut SizeCounter,
+    key_pending: bool,
+}
+
+impl ser::SerializeMap for MapCounter<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.key_pending {
+            return Err(Error::MapKeyWithoutValue);
+        }
+        self.key_pending = true;
+        key.serialize(&mut *self.counter)
+    }
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if !self.key_pending {
+            return Err(Error::MapValueWithoutKey);
+        }
+        self.key_pending = false;
+        value.serialize(&mut *self.counter)
+    }
+    fn end(self) -> Result<()> {
+        if self.key_pending {
+            return Err(Error::MapKeyWithoutValue);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+    use serde_bytes::ByteBuf;
+
+    use crate::{serialize_len, to_bytes};
+
+    fn assert_same_len<T: Serialize>(value: &T) {
+        assert_eq!(
+            serialize_len(value).unwrap(),
+            to_bytes(value).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_size_matches_encoding() {
+        #[derive(Serialize)]
+        enum Shape {
+            Point,
+            Circle(u64),
+            Rect { w: u32, h: u32 },
+        }
+
+        #[derive(Serialize)]
+        struct Everything {
+            name: String,
+            data: ByteBuf,
+            tags: Vec<String>,
+            map: BTreeMap<String, Option<i64>>,
+            shapes: Vec<Shape>,
+            pair: (u8, f64),
+            fixed: [u16; 3],
+            flag: bool,
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Some(-1));
+        map.insert("bcdef".to_string(), None);
+        let value = Everything {
+            name: "xdr".to_string(),
+            data: ByteBuf::from(vec![1, 2, 3, 4, 5]),
+            tags: vec!["".to_string(), "four".to_string()],
+            map,
+            shapes: vec![Shape::Point, Shape::Circle(7), Shape::Rect { w: 1, h: 2 }],
+            pair: (1, 2.5),
+            fixed: [1, 2, 3],
+            flag: true,
+        };
+        assert_same_len(&value);
+        assert_same_len(&"odd");
+        assert_same_len(&ByteBuf::from(vec![0u8; 8]));
+        assert_same_len(&Option::<u32>::None);
+    }
+
+    #[test]
+    fn test_size_of_recursive_type() {
+        #[derive(Serialize)]
+        struct Node {
+            next: Option<Box<Node>>,
+        }
+
+        let mut node = Node { next: None };
+        for _ in 0..200 {
+            node = Node {
+                next: Some(Box::new(node)),
+            };
+        }
+        assert_same_len(&node);
+        assert_eq!(serialize_len(&node).unwrap(), 201 * 4);
+    }
+}
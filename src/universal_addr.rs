@@ -0,0 +1,183 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::fmt::{self, Display};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use crate::Error;
+
+/// newtype name `XDRDeserializer` recognises, to check the address itself and
+/// fail with `Error::InvalidUniversalAddr` rather than a custom message
+pub(crate) const NEWTYPE_NAME: &str = "$xdr_brk::UniversalAddr";
+
+/// Socket address in the universal address format of rpcbind and NFSv4
+/// `netaddr4` (RFC 5665): the host followed by the two bytes of the port, as
+/// in `192.0.2.1.8.1` or `2001:db8::1.0.111` for ports 2049 and 111.
+///
+/// Encodes as an XDR `string`. Parsing or decoding a malformed address fails
+/// with `Error::InvalidUniversalAddr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UniversalAddr(pub SocketAddr);
+
+impl UniversalAddr {
+    pub fn new(ip: IpAddr, port: u16) -> Self {
+        UniversalAddr(SocketAddr::new(ip, port))
+    }
+
+    pub fn into_inner(self) -> SocketAddr {
+        self.0
+    }
+}
+
+impl From<SocketAddr> for UniversalAddr {
+    fn from(addr: SocketAddr) -> Self {
+        UniversalAddr(addr)
+    }
+}
+
+impl From<UniversalAddr> for SocketAddr {
+    fn from(addr: UniversalAddr) -> Self {
+        addr.0
+    }
+}
+
+impl Display for UniversalAddr {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let [high, low] = self.0.port().to_be_bytes();
+        write!(formatter, "{}.{}.{}", self.0.ip(), high, low)
+    }
+}
+
+impl FromStr for UniversalAddr {
+    type Err = Error;
+
+    fn from_str(uaddr: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidUniversalAddr(uaddr.to_string());
+        // only plain decimal, `u8::from_str` would also take a leading `+`
+        let port_byte = |part: &str| {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            part.parse::<u8>().ok()
+        };
+        let mut parts = uaddr.rsplitn(3, '.');
+        let low = parts.next().and_then(port_byte).ok_or_else(invalid)?;
+        let high = parts.next().and_then(port_byte).ok_or_else(invalid)?;
+        let ip: IpAddr = parts
+            .next()
+            .and_then(|host| host.parse().ok())
+            .ok_or_else(invalid)?;
+        Ok(UniversalAddr::new(ip, u16::from_be_bytes([high, low])))
+    }
+}
+
+impl TryFrom<&str> for UniversalAddr {
+    type Error = Error;
+
+    fn try_from(uaddr: &str) -> Result<Self, Error> {
+        uaddr.parse()
+    }
+}
+
+impl Serialize for UniversalAddr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UniversalAddr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = UniversalAddr;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a universal address string")
+            }
+
+            fn visit_str<E: de::Error>(self, uaddr: &str) -> Result<Self::Value, E> {
+                uaddr.parse().map_err(E::custom)
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_str(self)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(NEWTYPE_NAME, Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::UniversalAddr;
+    use crate::{Error, from_bytes, to_bytes};
+
+    #[test]
+    fn test_universal_addr() {
+        let addr: SocketAddr = "127.0.0.1:2049".parse().unwrap();
+        let uaddr = UniversalAddr::from(addr);
+        assert_eq!(uaddr.to_string(), "127.0.0.1.8.1");
+        assert_eq!("127.0.0.1.8.1".parse::<UniversalAddr>().unwrap(), uaddr);
+        let bytes = to_bytes(&uaddr).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0, 0, 0, 13, b'1', b'2', b'7', b'.', b'0', b'.', b'0', b'.', b'1', b'.', b'8',
+                b'.', b'1', 0, 0, 0,
+            ]
+        );
+        assert_eq!(from_bytes::<UniversalAddr>(&bytes).unwrap(), uaddr);
+
+        let addr: SocketAddr = "[2001:db8::1]:111".parse().unwrap();
+        let uaddr = UniversalAddr::from(addr);
+        assert_eq!(uaddr.to_string(), "2001:db8::1.0.111");
+        assert_eq!(UniversalAddr::try_from("2001:db8::1.0.111").unwrap(), uaddr);
+        assert_eq!(SocketAddr::from(uaddr), addr);
+        let bytes = to_bytes(&uaddr).unwrap();
+        assert_eq!(from_bytes::<UniversalAddr>(&bytes).unwrap(), uaddr);
+    }
+
+    #[test]
+    fn test_malformed_universal_addr() {
+        for uaddr in [
+            "",
+            "127.0.0.1",
+            "127.0.0.1.8",
+            "127.0.0.1.8.256",
+            "127.0.0.1.8.+1",
+            "127.0.0.1..1",
+            "127.0.0.1.8.1.",
+            "localhost.8.1",
+            "[::1].0.111",
+        ] {
+            let result = uaddr.parse::<UniversalAddr>();
+            assert!(
+                matches!(&result, Err(Error::InvalidUniversalAddr(s)) if s == uaddr),
+                "{uaddr}: {result:?}"
+            );
+        }
+
+        let bytes = to_bytes(&"10.0.0.1.8").unwrap();
+        let err = from_bytes::<UniversalAddr>(&bytes).unwrap_err();
+        assert!(
+            matches!(err.inner(), Error::InvalidUniversalAddr(s) if s == "10.0.0.1.8"),
+            "{err}"
+        );
+        let bytes = to_bytes(&("tcp", "10.0.0.1.8")).unwrap();
+        let err = from_bytes::<(String, UniversalAddr)>(&bytes).unwrap_err();
+        assert!(matches!(err.inner(), Error::InvalidUniversalAddr(_)));
+        assert_eq!(err.offset(), Some(24));
+    }
+}